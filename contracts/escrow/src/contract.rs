@@ -1,42 +1,116 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Addr, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult,
+    entry_point, from_binary, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut,
+    Env, MessageInfo, Order, Response, StdResult, Storage, Uint128,
 };
+use cw20::{Balance, Cw20CoinVerified, Cw20Contract, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_storage_plus::Bound;
+use cw_utils::NativeBalance;
 
 use crate::error::ContractError;
-use crate::msg::{ArbiterResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{Config, CONFIG};
-use cw2::set_contract_version;
+use crate::msg::{
+    ArbiterResponse, ConfigResponse, CreateMsg, DetailsResponse, ExecuteMsg, HistoryResponse,
+    InstantiateMsg, ListResponse, MigrateMsg, MilestoneMsg, MilestonesResponse, QueryMsg,
+    ReceiveMsg, VoteInfo, VotesResponse,
+};
+use crate::state::{
+    Config, Dispute, Escrow, EscrowEvent, FeeAmount, GenericBalance, HistoryEntry, Milestone,
+    RefundFeePolicy, Vote, CONFIG, ESCROWS, HISTORY, HISTORY_COUNT, LEGACY_CONFIG, VOTES,
+};
+use cw2::{get_contract_version, set_contract_version};
+use semver::Version;
+
+const MAX_BASIS_POINTS: u64 = 10_000;
 
 // Version info, for migration info
-const CONTRACT_NAME: &str = "crates.io:cw20-merkle-airdrop";
+const CONTRACT_NAME: &str = "crates.io:cw-escrow";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// Versions before 0.12 stored a single escrow, under a wrongly copied contract name
+const LEGACY_CONTRACT_NAME: &str = "crates.io:cw20-merkle-airdrop";
+/// id the escrow of a single escrow deployment is available under after migrating
+pub const LEGACY_ESCROW_ID: &str = "legacy";
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
-    env: Env,
-    info: MessageInfo,
+    _env: Env,
+    _info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    let config = Config {
-        arbiter: deps.api.addr_validate(&msg.arbiter)?,
-        recipient: deps.api.addr_validate(&msg.recipient)?,
-        source: info.sender,
-        expiration: msg.expiration,
-    };
-
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    if let Some(expiration) = msg.expiration {
-        if expiration.is_expired(&env.block) {
-            return Err(ContractError::Expired { expiration });
+    let mut arbiter_fee = msg.arbiter_fee;
+    if let Some(fee) = arbiter_fee.as_mut() {
+        match &mut fee.amount {
+            FeeAmount::Flat(coins) => *coins = normalize_coins(std::mem::take(coins)),
+            FeeAmount::BasisPoints(bps) => {
+                if *bps > MAX_BASIS_POINTS {
+                    return Err(ContractError::InvalidFee {});
+                }
+            }
         }
     }
-    CONFIG.save(deps.storage, &config)?;
+    // escrows themselves are created via ExecuteMsg::Create
+    CONFIG.save(deps.storage, &Config { arbiter_fee })?;
     Ok(Response::default())
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(mut deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME && stored.contract != LEGACY_CONTRACT_NAME {
+        return Err(ContractError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    let version: Version = CONTRACT_VERSION.parse()?;
+    let stored_version: Version = stored.version.parse()?;
+    if stored_version > version {
+        return Err(ContractError::CannotMigrateVersion {
+            previous_version: stored.version,
+        });
+    }
+
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("from_version", stored.version);
+    if stored.contract == LEGACY_CONTRACT_NAME {
+        migrate_legacy_config(deps.branch(), &env)?;
+        res = res.add_attribute("legacy_escrow", LEGACY_ESCROW_ID);
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(res)
+}
+
+// moves the single escrow of an old deployment into the escrow map
+fn migrate_legacy_config(deps: DepsMut, env: &Env) -> Result<(), ContractError> {
+    let legacy = LEGACY_CONFIG.load(deps.storage)?;
+    // the old contract paid out whatever it held, so all of it belongs to the escrow
+    let native = deps.querier.query_all_balances(&env.contract.address)?;
+
+    let escrow = Escrow {
+        arbiters: vec![legacy.arbiter],
+        threshold: 1,
+        recipient: legacy.recipient,
+        source: legacy.source,
+        expiration: legacy.expiration,
+        auto_release: None,
+        balance: GenericBalance {
+            native: normalize_coins(native),
+            cw20: vec![],
+        },
+        milestones: vec![],
+        dispute: None,
+        cw20_whitelist: vec![],
+    };
+    ESCROWS.save(deps.storage, LEGACY_ESCROW_ID, &escrow)?;
+    // overwrites the legacy config, which was stored under the same key
+    CONFIG.save(deps.storage, &Config { arbiter_fee: None })?;
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -45,133 +119,946 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::Approve { quantity } => execute_approve(deps, env, info, quantity),
-        ExecuteMsg::Refund {} => execute_refund(deps, env, info),
+        ExecuteMsg::Create(msg) => {
+            execute_create(deps, env, msg, info.sender, Balance::from(info.funds))
+        }
+        ExecuteMsg::Approve { id, quantity } => execute_approve(deps, env, info, id, quantity),
+        ExecuteMsg::ApproveMilestone { id } => execute_approve_milestone(deps, env, info, id),
+        ExecuteMsg::Reject { id } => execute_reject(deps, info, id),
+        ExecuteMsg::Refund { id } => execute_refund(deps, env, info, id),
+        ExecuteMsg::Dispute { id, reason } => execute_dispute(deps, info, id, reason),
+        ExecuteMsg::Resolve {
+            id,
+            to_recipient,
+            to_source,
+        } => execute_resolve(deps, env, info, id, to_recipient, to_source),
+        ExecuteMsg::TopUp { id } => {
+            execute_top_up(deps, env, id, info.sender, Balance::from(info.funds))
+        }
+        ExecuteMsg::Claim { id } => execute_claim(deps, env, info, id),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+    }
+}
+
+fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapped: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // the sender is the cw20 contract, tokens are tracked per contract address
+    let balance = Balance::Cw20(Cw20CoinVerified {
+        address: info.sender,
+        amount: wrapped.amount,
+    });
+
+    let msg: ReceiveMsg = from_binary(&wrapped.msg)?;
+    let sender = deps.api.addr_validate(&wrapped.sender)?;
+    match msg {
+        ReceiveMsg::Create(msg) => execute_create(deps, env, msg, sender, balance),
+        ReceiveMsg::TopUp { id } => execute_top_up(deps, env, id, sender, balance),
+    }
+}
+
+fn execute_create(
+    deps: DepsMut,
+    env: Env,
+    msg: CreateMsg,
+    source: Addr,
+    mut balance: Balance,
+) -> Result<Response, ContractError> {
+    balance.normalize();
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+
+    if let Some(expiration) = msg.expiration {
+        if expiration.is_expired(&env.block) {
+            return Err(ContractError::Expired { expiration });
+        }
+    }
+
+    if let (Some(auto_release), Some(expiration)) = (msg.auto_release, msg.expiration) {
+        // a height cannot be compared to a time, those are only checked when claiming
+        if auto_release >= expiration {
+            return Err(ContractError::InvalidAutoRelease {});
+        }
+    }
+
+    let mut arbiters = msg
+        .arbiters
+        .iter()
+        .map(|arbiter| deps.api.addr_validate(arbiter))
+        .collect::<StdResult<Vec<_>>>()?;
+    arbiters.sort();
+    arbiters.dedup();
+    if msg.threshold == 0
+        || msg.threshold as usize > arbiters.len()
+        || arbiters.len() != msg.arbiters.len()
+    {
+        return Err(ContractError::InvalidThreshold {});
+    }
+
+    let milestones = msg
+        .milestones
+        .into_iter()
+        .map(|milestone| validate_milestone(deps.as_ref(), milestone))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut cw20_whitelist = msg
+        .cw20_whitelist
+        .iter()
+        .map(|token| deps.api.addr_validate(token))
+        .collect::<StdResult<Vec<_>>>()?;
+    if let Balance::Cw20(token) = &balance {
+        cw20_whitelist.push(token.address.clone());
+    }
+    for milestone in &milestones {
+        cw20_whitelist.extend(
+            milestone
+                .amount
+                .cw20
+                .iter()
+                .map(|token| token.address.clone()),
+        );
+    }
+    cw20_whitelist.sort();
+    cw20_whitelist.dedup();
+
+    let mut escrow = Escrow {
+        arbiters,
+        threshold: msg.threshold,
+        recipient: deps.api.addr_validate(&msg.recipient)?,
+        source: source.clone(),
+        expiration: msg.expiration,
+        auto_release: msg.auto_release,
+        balance: GenericBalance::default(),
+        milestones,
+        dispute: None,
+        cw20_whitelist,
+    };
+    escrow.balance.add_tokens(balance);
+
+    // try to store it, fail if the id was already in use
+    ESCROWS.update(deps.storage, &msg.id, |existing| match existing {
+        None => Ok(escrow.clone()),
+        Some(_) => Err(ContractError::AlreadyInUse {}),
+    })?;
+    record_history(
+        deps.storage,
+        &env,
+        &msg.id,
+        EscrowEvent::Create,
+        source,
+        escrow.balance,
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create")
+        .add_attribute("id", msg.id))
+}
+
+fn validate_milestone(deps: Deps, msg: MilestoneMsg) -> Result<Milestone, ContractError> {
+    let mut amount = GenericBalance::default();
+    amount.add_tokens(Balance::from(msg.amount));
+    for token in msg.cw20_amount {
+        amount.add_tokens(Balance::Cw20(Cw20CoinVerified {
+            address: deps.api.addr_validate(&token.address)?,
+            amount: token.amount,
+        }));
+    }
+    if amount.is_empty() {
+        return Err(ContractError::EmptyMilestone {});
+    }
+
+    Ok(Milestone {
+        title: msg.title,
+        amount,
+        deadline: msg.deadline,
+        arbiter: msg
+            .arbiter
+            .map(|arbiter| deps.api.addr_validate(&arbiter))
+            .transpose()?,
+        released: false,
+    })
+}
+
+fn execute_top_up(
+    deps: DepsMut,
+    env: Env,
+    id: String,
+    sender: Addr,
+    mut balance: Balance,
+) -> Result<Response, ContractError> {
+    balance.normalize();
+    if balance.is_empty() {
+        return Err(ContractError::EmptyBalance {});
+    }
+
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if let Balance::Cw20(token) = &balance {
+        if !escrow.cw20_whitelist.contains(&token.address) {
+            return Err(ContractError::NotInWhitelist {});
+        }
     }
+    escrow.balance.add_tokens(balance.clone());
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+    let mut deposit = GenericBalance::default();
+    deposit.add_tokens(balance);
+    record_history(deps.storage, &env, &id, EscrowEvent::TopUp, sender, deposit)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "top_up")
+        .add_attribute("id", id))
 }
 
 fn execute_approve(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    id: String,
     quantity: Option<Vec<Coin>>,
 ) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    if info.sender != config.arbiter {
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if !escrow.arbiters.contains(&info.sender) {
         return Err(ContractError::Unauthorized {});
     }
 
-    // throws error if the contract is expired
-    if let Some(expiration) = config.expiration {
+    // throws error if the escrow is expired
+    if let Some(expiration) = escrow.expiration {
         if expiration.is_expired(&env.block) {
             return Err(ContractError::Expired { expiration });
         }
     }
 
+    // milestone payment plans have to be released via ApproveMilestone
+    if escrow.next_milestone().is_some() {
+        return Err(ContractError::PendingMilestones {});
+    }
+
+    if quantity.is_some() && escrow.threshold > 1 {
+        return Err(ContractError::PartialRelease {});
+    }
+    if tally_vote(deps.storage, &id, &info.sender, &Vote::Approve)? < escrow.threshold {
+        let approvals = cast_vote(deps.storage, &id, &info.sender, Vote::Approve)?;
+        return Ok(vote_response(id, "approve", approvals));
+    }
+
     let amount = if let Some(quantity) = quantity {
-        quantity
+        let amount = GenericBalance {
+            native: quantity,
+            cw20: vec![],
+        };
+        // errors if the escrow does not hold enough tokens
+        escrow.balance.sub_tokens(&amount)?;
+        amount
     } else {
         // release everything
-        // Querier guarantees to return up-to-date data, including funds sent in this handle message
-        // https://github.com/CosmWasm/wasmd/blob/master/x/wasm/internal/keeper/keeper.go#L185-L192
-        deps.querier.query_all_balances(&env.contract.address)?
+        std::mem::take(&mut escrow.balance)
     };
-    Ok(send_tokens(config.recipient, amount, "approve"))
+    let fee = CONFIG.load(deps.storage)?.arbiter_fee.map(|fee| fee.amount);
+    let res = settle(
+        fee,
+        escrow.recipient.clone(),
+        amount.clone(),
+        &escrow.arbiters,
+        "approve",
+    )?;
+
+    if escrow.is_closed() {
+        ESCROWS.remove(deps.storage, &id);
+    } else {
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+    }
+    clear_votes(deps.storage, &id)?;
+    record_history(
+        deps.storage,
+        &env,
+        &id,
+        EscrowEvent::Approve,
+        info.sender,
+        amount,
+    )?;
+    Ok(res.add_attribute("id", id))
 }
 
-fn execute_refund(deps: DepsMut, env: Env, _info: MessageInfo) -> Result<Response, ContractError> {
-    let config = CONFIG.load(deps.storage)?;
-    // anyone can try to refund, as long as the contract is expired
-    if let Some(expiration) = config.expiration {
-        if !expiration.is_expired(&env.block) {
-            return Err(ContractError::NotExpired {});
+fn execute_approve_milestone(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+
+    // throws error if the escrow is expired
+    if let Some(expiration) = escrow.expiration {
+        if expiration.is_expired(&env.block) {
+            return Err(ContractError::Expired { expiration });
+        }
+    }
+
+    let (index, milestone) = escrow
+        .next_milestone()
+        .ok_or(ContractError::NoPendingMilestone {})?;
+    let milestone = milestone.clone();
+    let authorized = match &milestone.arbiter {
+        Some(arbiter) => &info.sender == arbiter,
+        None => escrow.arbiters.contains(&info.sender),
+    };
+    if !authorized {
+        return Err(ContractError::Unauthorized {});
+    }
+    if let Some(deadline) = milestone.deadline {
+        if deadline.is_expired(&env.block) {
+            return Err(ContractError::MilestoneExpired { deadline });
         }
+    }
+
+    // without an own arbiter, the escrow arbiters vote on the milestone
+    if milestone.arbiter.is_none()
+        && tally_vote(deps.storage, &id, &info.sender, &Vote::Approve)? < escrow.threshold
+    {
+        let approvals = cast_vote(deps.storage, &id, &info.sender, Vote::Approve)?;
+        return Ok(vote_response(id, "approve", approvals));
+    }
+
+    // errors if the escrow does not hold enough tokens for this milestone
+    let amount = milestone.amount;
+    escrow.balance.sub_tokens(&amount)?;
+    escrow.milestones[index].released = true;
+
+    // a milestone arbiter is paid on its own, otherwise the escrow arbiters share the fee
+    let arbiters = match milestone.arbiter {
+        Some(arbiter) => vec![arbiter],
+        None => escrow.arbiters.clone(),
+    };
+    let fee = CONFIG.load(deps.storage)?.arbiter_fee.map(|fee| fee.amount);
+    let res = settle(
+        fee,
+        escrow.recipient.clone(),
+        amount.clone(),
+        &arbiters,
+        "approve_milestone",
+    )?;
+
+    if escrow.is_closed() {
+        ESCROWS.remove(deps.storage, &id);
     } else {
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+    }
+    clear_votes(deps.storage, &id)?;
+    record_history(
+        deps.storage,
+        &env,
+        &id,
+        EscrowEvent::Approve,
+        info.sender,
+        amount,
+    )?;
+    Ok(res
+        .add_attribute("id", id)
+        .add_attribute("milestone", index.to_string()))
+}
+
+fn execute_reject(deps: DepsMut, info: MessageInfo, id: String) -> Result<Response, ContractError> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    if !escrow.arbiters.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let rejections = cast_vote(deps.storage, &id, &info.sender, Vote::Reject)?;
+    Ok(vote_response(id, "reject", rejections))
+}
+
+fn execute_refund(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    // anyone can try to refund, as long as the escrow is expired, the next milestone
+    // missed its deadline or enough arbiters rejected the escrow
+    let expired =
+        matches!(escrow.expiration, Some(expiration) if expiration.is_expired(&env.block));
+    let missed_deadline = matches!(
+        escrow.next_milestone().and_then(|(_, milestone)| milestone.deadline),
+        Some(deadline) if deadline.is_expired(&env.block)
+    );
+    let rejected = count_votes(deps.storage, &id, Vote::Reject)? >= escrow.rejection_threshold();
+    // a dispute freezes expiry based refunds until the arbiters decide
+    if escrow.dispute.is_some() && !rejected {
+        return Err(ContractError::Disputed {});
+    }
+    if !expired && !missed_deadline && !rejected {
         return Err(ContractError::NotExpired {});
     }
 
-    // Querier guarantees to return up-to-date data, including funds sent in this handle message
-    // https://github.com/CosmWasm/wasmd/blob/master/x/wasm/internal/keeper/keeper.go#L185-L192
-    let balance = deps.querier.query_all_balances(&env.contract.address)?;
-    Ok(send_tokens(config.source, balance, "refund"))
+    let fee = CONFIG
+        .load(deps.storage)?
+        .arbiter_fee
+        .filter(|fee| fee.refund_policy == RefundFeePolicy::Charge)
+        .map(|fee| fee.amount);
+    let res = settle(
+        fee,
+        escrow.source,
+        escrow.balance.clone(),
+        &escrow.arbiters,
+        "refund",
+    )?;
+
+    ESCROWS.remove(deps.storage, &id);
+    clear_votes(deps.storage, &id)?;
+    record_history(
+        deps.storage,
+        &env,
+        &id,
+        EscrowEvent::Refund,
+        info.sender,
+        escrow.balance,
+    )?;
+    Ok(res.add_attribute("id", id))
 }
 
-// this is a helper to move the tokens, so the business logic is easy to read
-fn send_tokens(to_address: Addr, amount: Vec<Coin>, action: &str) -> Response {
-    Response::new()
-        .add_message(BankMsg::Send {
-            to_address: to_address.clone().into(),
-            amount,
+fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+) -> Result<Response, ContractError> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.recipient {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // claims are possible from the auto release until the escrow expires
+    if let Some(expiration) = escrow.expiration {
+        if expiration.is_expired(&env.block) {
+            return Err(ContractError::Expired { expiration });
+        }
+    }
+    let released =
+        matches!(escrow.auto_release, Some(auto_release) if auto_release.is_expired(&env.block));
+    if !released {
+        return Err(ContractError::NotClaimable {});
+    }
+    if escrow.dispute.is_some() {
+        return Err(ContractError::Disputed {});
+    }
+
+    // pays out everything, including the amounts of pending milestones
+    ESCROWS.remove(deps.storage, &id);
+    clear_votes(deps.storage, &id)?;
+    record_history(
+        deps.storage,
+        &env,
+        &id,
+        EscrowEvent::Claim,
+        info.sender,
+        escrow.balance.clone(),
+    )?;
+    // the arbiters did not act, so they are not paid a fee
+    let res = settle(
+        None,
+        escrow.recipient,
+        escrow.balance,
+        &escrow.arbiters,
+        "claim",
+    )?;
+    Ok(res.add_attribute("id", id))
+}
+
+fn execute_dispute(
+    deps: DepsMut,
+    info: MessageInfo,
+    id: String,
+    reason: String,
+) -> Result<Response, ContractError> {
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if info.sender != escrow.source && info.sender != escrow.recipient {
+        return Err(ContractError::Unauthorized {});
+    }
+    if escrow.dispute.is_some() {
+        return Err(ContractError::AlreadyDisputed {});
+    }
+
+    escrow.dispute = Some(Dispute {
+        raised_by: info.sender.clone(),
+        reason,
+    });
+    ESCROWS.save(deps.storage, &id, &escrow)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "dispute")
+        .add_attribute("id", id)
+        .add_attribute("raised_by", info.sender))
+}
+
+fn execute_resolve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: String,
+    to_recipient: Vec<Coin>,
+    to_source: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let mut escrow = ESCROWS.load(deps.storage, &id)?;
+    if !escrow.arbiters.contains(&info.sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if escrow.dispute.is_none() {
+        return Err(ContractError::NotDisputed {});
+    }
+
+    // normalized, so arbiters voting for the same split are counted together
+    let to_recipient = normalize_coins(to_recipient);
+    let to_source = normalize_coins(to_source);
+    let vote = Vote::Resolve {
+        to_recipient: to_recipient.clone(),
+        to_source: to_source.clone(),
+    };
+    let approvals = cast_vote(deps.storage, &id, &info.sender, vote)?;
+    if approvals < escrow.threshold {
+        return Ok(vote_response(id, "resolve", approvals));
+    }
+
+    // errors if the escrow does not hold enough tokens for the split
+    for amount in [&to_recipient, &to_source] {
+        escrow.balance.sub_tokens(&GenericBalance {
+            native: amount.clone(),
+            cw20: vec![],
+        })?;
+    }
+    escrow.dispute = None;
+
+    if escrow.is_closed() {
+        ESCROWS.remove(deps.storage, &id);
+    } else {
+        ESCROWS.save(deps.storage, &id, &escrow)?;
+    }
+    clear_votes(deps.storage, &id)?;
+    let paid = GenericBalance {
+        native: normalize_coins([to_recipient.clone(), to_source.clone()].concat()),
+        cw20: vec![],
+    };
+    record_history(
+        deps.storage,
+        &env,
+        &id,
+        EscrowEvent::Resolve,
+        info.sender,
+        paid,
+    )?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "resolve")
+        .add_attribute("id", id);
+    for (to_address, amount) in [(escrow.recipient, to_recipient), (escrow.source, to_source)] {
+        if !amount.is_empty() {
+            res = res.add_message(BankMsg::Send {
+                to_address: to_address.into(),
+                amount,
+            });
+        }
+    }
+    Ok(res)
+}
+
+fn normalize_coins(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut balance = NativeBalance(coins);
+    balance.normalize();
+    balance.into_vec()
+}
+
+// records the vote of an arbiter and returns how many arbiters voted the same way
+fn cast_vote(storage: &mut dyn Storage, id: &str, arbiter: &Addr, vote: Vote) -> StdResult<u32> {
+    VOTES.save(storage, (id, arbiter), &vote)?;
+    count_votes(storage, id, vote)
+}
+
+// counts the votes an arbiter would join, without recording its vote
+fn tally_vote(storage: &dyn Storage, id: &str, arbiter: &Addr, vote: &Vote) -> StdResult<u32> {
+    let others = VOTES
+        .prefix(id)
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((voter, v)) => voter != arbiter && v == vote,
+            Err(_) => true,
         })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(others.len() as u32 + 1)
+}
+
+fn count_votes(storage: &dyn Storage, id: &str, vote: Vote) -> StdResult<u32> {
+    let votes = VOTES
+        .prefix(id)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(votes.into_iter().filter(|(_, v)| *v == vote).count() as u32)
+}
+
+// votes only apply to the next release, so they are dropped once tokens are paid out
+fn clear_votes(storage: &mut dyn Storage, id: &str) -> StdResult<()> {
+    let arbiters = VOTES
+        .prefix(id)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for arbiter in arbiters {
+        VOTES.remove(storage, (id, &arbiter));
+    }
+    Ok(())
+}
+
+// appends an event to the audit trail
+fn record_history(
+    storage: &mut dyn Storage,
+    env: &Env,
+    id: &str,
+    event: EscrowEvent,
+    actor: Addr,
+    coins: GenericBalance,
+) -> StdResult<()> {
+    let index = HISTORY_COUNT.may_load(storage)?.unwrap_or_default() + 1;
+    HISTORY_COUNT.save(storage, &index)?;
+    let entry = HistoryEntry {
+        index,
+        id: id.to_string(),
+        event,
+        height: env.block.height,
+        time: env.block.time,
+        actor,
+        coins,
+    };
+    HISTORY.save(storage, index, &entry)
+}
+
+fn vote_response(id: String, vote: &str, count: u32) -> Response {
+    Response::new()
+        .add_attribute("action", "vote")
+        .add_attribute("id", id)
+        .add_attribute("vote", vote)
+        .add_attribute("count", count.to_string())
+}
+
+// pays out a settlement, sending the arbiter fee (if any) to the given arbiters
+fn settle(
+    fee: Option<FeeAmount>,
+    to_address: Addr,
+    amount: GenericBalance,
+    arbiters: &[Addr],
+    action: &str,
+) -> Result<Response, ContractError> {
+    let (payout, fee) = match fee {
+        Some(fee) => deduct_fee(&fee, amount)?,
+        None => (amount, GenericBalance::default()),
+    };
+
+    let mut res = Response::new()
+        .add_messages(transfer_msgs(&to_address, &payout)?)
         .add_attribute("action", action)
         .add_attribute("to", to_address)
+        .add_attribute("payout", payout.to_string())
+        .add_attribute("arbiter_fee", fee.to_string());
+    for (arbiter, share) in split_fee(&fee, arbiters) {
+        res = res.add_messages(transfer_msgs(&arbiter, &share)?);
+    }
+    Ok(res)
+}
+
+// splits the arbiter fee off a payout, returns the remaining payout and the fee
+fn deduct_fee(
+    fee: &FeeAmount,
+    mut amount: GenericBalance,
+) -> StdResult<(GenericBalance, GenericBalance)> {
+    let fee = match fee {
+        // capped at the payout, so a small payout (or one in other denoms) is never blocked
+        FeeAmount::Flat(coins) => GenericBalance {
+            native: coins
+                .iter()
+                .filter_map(|coin| {
+                    let held = amount.native.iter().find(|c| c.denom == coin.denom)?;
+                    Some(Coin {
+                        denom: coin.denom.clone(),
+                        amount: coin.amount.min(held.amount),
+                    })
+                })
+                .collect(),
+            cw20: vec![],
+        },
+        FeeAmount::BasisPoints(bps) => GenericBalance {
+            native: amount
+                .native
+                .iter()
+                .map(|coin| Coin {
+                    denom: coin.denom.clone(),
+                    amount: coin.amount.multiply_ratio(*bps, MAX_BASIS_POINTS),
+                })
+                .filter(|coin| !coin.amount.is_zero())
+                .collect(),
+            cw20: amount
+                .cw20
+                .iter()
+                .map(|token| Cw20CoinVerified {
+                    address: token.address.clone(),
+                    amount: token.amount.multiply_ratio(*bps, MAX_BASIS_POINTS),
+                })
+                .filter(|token| !token.is_empty())
+                .collect(),
+        },
+    };
+    amount.sub_tokens(&fee)?;
+    Ok((amount, fee))
+}
+
+// the fee is shared equally between the arbiters, the first one also gets the remainder
+fn split_fee(fee: &GenericBalance, arbiters: &[Addr]) -> Vec<(Addr, GenericBalance)> {
+    let count = arbiters.len() as u128;
+    let share = |amount: Uint128, index: usize| {
+        let share = amount.u128() / count;
+        if index == 0 {
+            Uint128::new(amount.u128() - share * (count - 1))
+        } else {
+            Uint128::new(share)
+        }
+    };
+
+    arbiters
+        .iter()
+        .enumerate()
+        .map(|(index, arbiter)| {
+            let native = fee
+                .native
+                .iter()
+                .map(|coin| Coin {
+                    denom: coin.denom.clone(),
+                    amount: share(coin.amount, index),
+                })
+                .filter(|coin| !coin.amount.is_zero())
+                .collect();
+            let cw20 = fee
+                .cw20
+                .iter()
+                .map(|token| Cw20CoinVerified {
+                    address: token.address.clone(),
+                    amount: share(token.amount, index),
+                })
+                .filter(|token| !token.is_empty())
+                .collect();
+            (arbiter.clone(), GenericBalance { native, cw20 })
+        })
+        .collect()
+}
+
+// this is a helper to move the tokens, so the business logic is easy to read
+fn transfer_msgs(to_address: &Addr, amount: &GenericBalance) -> StdResult<Vec<CosmosMsg>> {
+    let mut msgs = vec![];
+    if !amount.native.is_empty() {
+        msgs.push(
+            BankMsg::Send {
+                to_address: to_address.into(),
+                amount: amount.native.clone(),
+            }
+            .into(),
+        );
+    }
+    for token in &amount.cw20 {
+        // Cw20Contract is a function helper that provides several queries and message builder.
+        msgs.push(
+            Cw20Contract(token.address.clone()).call(Cw20ExecuteMsg::Transfer {
+                recipient: to_address.into(),
+                amount: token.amount,
+            })?,
+        );
+    }
+    Ok(msgs)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Arbiter {} => to_binary(&query_arbiter(deps)?),
+        QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Arbiter { id } => to_binary(&query_arbiter(deps, id)?),
+        QueryMsg::Votes { id } => to_binary(&query_votes(deps, id)?),
+        QueryMsg::List { start_after, limit } => to_binary(&query_list(deps, start_after, limit)?),
+        QueryMsg::Details { id } => to_binary(&query_details(deps, id)?),
+        QueryMsg::Milestones { id } => to_binary(&query_milestones(deps, id)?),
+        QueryMsg::History { start_after, limit } => {
+            to_binary(&query_history(deps, start_after, limit)?)
+        }
     }
 }
 
-fn query_arbiter(deps: Deps) -> StdResult<ArbiterResponse> {
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config = CONFIG.load(deps.storage)?;
-    let addr = config.arbiter;
-    Ok(ArbiterResponse { arbiter: addr })
+    Ok(ConfigResponse {
+        arbiter_fee: config.arbiter_fee,
+    })
+}
+
+fn query_arbiter(deps: Deps, id: String) -> StdResult<ArbiterResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    Ok(ArbiterResponse {
+        arbiters: escrow.arbiters,
+        threshold: escrow.threshold,
+        dispute: escrow.dispute,
+    })
+}
+
+fn query_votes(deps: Deps, id: String) -> StdResult<VotesResponse> {
+    let votes = VOTES
+        .prefix(&id)
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(arbiter, vote)| VoteInfo { arbiter, vote }))
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(VotesResponse { votes })
+}
+
+fn query_details(deps: Deps, id: String) -> StdResult<DetailsResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    Ok(DetailsResponse {
+        id,
+        arbiters: escrow.arbiters,
+        threshold: escrow.threshold,
+        recipient: escrow.recipient,
+        source: escrow.source,
+        expiration: escrow.expiration,
+        auto_release: escrow.auto_release,
+        balance: escrow.balance,
+        dispute: escrow.dispute,
+        cw20_whitelist: escrow.cw20_whitelist,
+    })
+}
+
+fn query_milestones(deps: Deps, id: String) -> StdResult<MilestonesResponse> {
+    let escrow = ESCROWS.load(deps.storage, &id)?;
+    Ok(MilestonesResponse {
+        milestones: escrow.milestones,
+    })
+}
+
+// Limits for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn query_list(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let escrows: StdResult<Vec<_>> = ESCROWS
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect();
+    Ok(ListResponse { escrows: escrows? })
+}
+
+fn query_history(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<HistoryResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let entries: StdResult<Vec<_>> = HISTORY
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, entry)| entry))
+        .collect();
+    Ok(HistoryResponse { entries: entries? })
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::state::{ArbiterFee, LegacyConfig};
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coins, CosmosMsg, Timestamp};
+    use cosmwasm_std::{attr, coin, coins, Timestamp, WasmMsg};
     use cw_utils::Expiration;
 
-    fn init_msg_expire_by_height(expiration: Option<Expiration>) -> InstantiateMsg {
-        InstantiateMsg {
-            arbiter: String::from("verifies"),
+    fn create_msg(id: &str, expiration: Option<Expiration>) -> CreateMsg {
+        CreateMsg {
+            id: id.to_string(),
+            arbiters: vec![String::from("verifies")],
+            threshold: 1,
             recipient: String::from("benefits"),
             expiration,
+            auto_release: None,
+            milestones: vec![],
+            cw20_whitelist: vec![],
         }
     }
 
+    fn create_msg_expire_by_height(id: &str, expiration: Option<Expiration>) -> ExecuteMsg {
+        ExecuteMsg::Create(create_msg(id, expiration))
+    }
+
+    fn mock_env_height(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env.block.time = Timestamp::from_seconds(0);
+        env
+    }
+
     #[test]
     fn proper_initialization() {
         let mut deps = mock_dependencies();
 
-        let msg = init_msg_expire_by_height(Some(Expiration::AtHeight(1000)));
-        let mut env = mock_env();
-        env.block.height = 876;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info("creator", &coins(1000, "earth"));
+        let info = mock_info("creator", &[]);
+        let res = instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+        assert_eq!(0, res.messages.len());
 
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+        // create an escrow
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
 
         // it worked, let's query the state
-        let state = CONFIG.load(&deps.storage).unwrap();
+        let state = ESCROWS.load(&deps.storage, "foobar").unwrap();
         assert_eq!(
             state,
-            Config {
-                arbiter: Addr::unchecked("verifies"),
+            Escrow {
+                arbiters: vec![Addr::unchecked("verifies")],
+                threshold: 1,
                 recipient: Addr::unchecked("benefits"),
                 source: Addr::unchecked("creator"),
-                expiration: Some(Expiration::AtHeight(1000))
+                expiration: Some(Expiration::AtHeight(1000)),
+                auto_release: None,
+                balance: GenericBalance {
+                    native: coins(1000, "earth"),
+                    cw20: vec![],
+                },
+                milestones: vec![],
+                dispute: None,
+                cw20_whitelist: vec![],
             }
         );
     }
 
     #[test]
-    fn cannot_initialize_expired() {
+    fn cannot_create_expired() {
         let mut deps = mock_dependencies();
 
-        let msg = init_msg_expire_by_height(Some(Expiration::AtHeight(1000)));
-        let mut env = mock_env();
-        env.block.height = 1001;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info("creator", &coins(1000, "earth"));
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(1001),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
 
-        let res = instantiate(deps.as_mut(), env, info, msg);
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let res = execute(deps.as_mut(), mock_env_height(1001), info, msg);
         match res.unwrap_err() {
             ContractError::Expired { .. } => {}
             e => panic!("unexpected error: {:?}", e),
@@ -179,95 +1066,182 @@ mod tests {
     }
 
     #[test]
-    fn init_and_query() {
+    fn cannot_create_empty_or_duplicate() {
         let mut deps = mock_dependencies();
 
-        let arbiter = Addr::unchecked("arbiters");
-        let recipient = Addr::unchecked("receives");
-        let creator = Addr::unchecked("creates");
-        let msg = InstantiateMsg {
-            arbiter: arbiter.clone().into(),
-            recipient: recipient.into(),
-            expiration: None,
-        };
-        let mut env = mock_env();
-        env.block.height = 876;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info(creator.as_str(), &[]);
-        let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
-
-        // now let's query
-        let query_response = query_arbiter(deps.as_ref()).unwrap();
-        assert_eq!(query_response.arbiter, arbiter);
-    }
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        // no funds sent
+        let msg = create_msg_expire_by_height("foobar", None);
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(876), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::EmptyBalance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // first one works, second one with the same id fails
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info.clone(),
+            msg.clone(),
+        )
+        .unwrap();
+        let res = execute(deps.as_mut(), mock_env_height(876), info, msg);
+        match res.unwrap_err() {
+            ContractError::AlreadyInUse {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn create_and_query() {
+        let mut deps = mock_dependencies();
+
+        let arbiter = Addr::unchecked("arbiters");
+        let recipient = Addr::unchecked("receives");
+        let creator = Addr::unchecked("creates");
+        let info = mock_info(creator.as_str(), &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        for id in ["foo", "bar", "baz"] {
+            let msg = ExecuteMsg::Create(CreateMsg {
+                id: id.to_string(),
+                arbiters: vec![arbiter.clone().into()],
+                threshold: 1,
+                recipient: recipient.clone().into(),
+                expiration: None,
+                auto_release: None,
+                milestones: vec![],
+                cw20_whitelist: vec![],
+            });
+            let info = mock_info(creator.as_str(), &coins(100, "earth"));
+            let res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+            assert_eq!(0, res.messages.len());
+        }
+
+        // now let's query
+        let query_response = query_arbiter(deps.as_ref(), "foo".to_string()).unwrap();
+        assert_eq!(query_response.arbiters, vec![arbiter.clone()]);
+        assert_eq!(query_response.threshold, 1);
+
+        let details = query_details(deps.as_ref(), "bar".to_string()).unwrap();
+        assert_eq!(
+            details,
+            DetailsResponse {
+                id: "bar".to_string(),
+                arbiters: vec![arbiter],
+                threshold: 1,
+                recipient,
+                source: creator,
+                expiration: None,
+                auto_release: None,
+                balance: GenericBalance {
+                    native: coins(100, "earth"),
+                    cw20: vec![],
+                },
+                dispute: None,
+                cw20_whitelist: vec![],
+            }
+        );
+
+        // list is ordered by id and paginated
+        let list = query_list(deps.as_ref(), None, None).unwrap();
+        assert_eq!(list.escrows, vec!["bar", "baz", "foo"]);
+        let list = query_list(deps.as_ref(), None, Some(2)).unwrap();
+        assert_eq!(list.escrows, vec!["bar", "baz"]);
+        let list = query_list(deps.as_ref(), Some("baz".to_string()), Some(2)).unwrap();
+        assert_eq!(list.escrows, vec!["foo"]);
+    }
 
     #[test]
     fn execute_approve() {
         let mut deps = mock_dependencies();
 
         // initialize the store
-        let init_amount = coins(1000, "earth");
-        let msg = init_msg_expire_by_height(Some(Expiration::AtHeight(1000)));
-        let mut env = mock_env();
-        env.block.height = 876;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info("creator", &init_amount);
-        let contract_addr = env.clone().contract.address;
-        let init_res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, init_res.messages.len());
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
 
-        // balance changed in init
-        deps.querier.update_balance(&contract_addr, init_amount);
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let create_res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        assert_eq!(0, create_res.messages.len());
 
         // beneficiary cannot release it
-        let msg = ExecuteMsg::Approve { quantity: None };
-        let mut env = mock_env();
-        env.block.height = 900;
-        env.block.time = Timestamp::from_seconds(0);
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
         let info = mock_info("beneficiary", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg.clone());
+        let execute_res = execute(deps.as_mut(), mock_env_height(900), info, msg.clone());
         match execute_res.unwrap_err() {
             ContractError::Unauthorized { .. } => {}
             e => panic!("unexpected error: {:?}", e),
         }
 
         // verifier cannot release it when expired
-        let mut env = mock_env();
-        env.block.height = 1100;
-        env.block.time = Timestamp::from_seconds(0);
         let info = mock_info("verifies", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg.clone());
+        let execute_res = execute(deps.as_mut(), mock_env_height(1100), info, msg.clone());
         match execute_res.unwrap_err() {
             ContractError::Expired { .. } => {}
             e => panic!("unexpected error: {:?}", e),
         }
 
-        // complete release by verifier, before expiration
-        let mut env = mock_env();
-        env.block.height = 999;
-        env.block.time = Timestamp::from_seconds(0);
+        // partial release by verifier, before expiration
+        let partial_msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: Some(coins(500, "earth")),
+        };
         let info = mock_info("verifies", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let execute_res = execute(deps.as_mut(), mock_env_height(999), info, partial_msg).unwrap();
         assert_eq!(1, execute_res.messages.len());
         let msg = execute_res.messages.get(0).expect("no message");
         assert_eq!(
             msg.msg,
             CosmosMsg::Bank(BankMsg::Send {
                 to_address: "benefits".into(),
-                amount: coins(1000, "earth"),
+                amount: coins(500, "earth"),
             })
         );
+        let escrow = ESCROWS.load(&deps.storage, "foobar").unwrap();
+        assert_eq!(escrow.balance.native, coins(500, "earth"));
 
-        // partial release by verifier, before expiration
-        let partial_msg = ExecuteMsg::Approve {
-            quantity: Some(coins(500, "earth")),
+        // cannot release more than what is left
+        let too_much_msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: Some(coins(501, "earth")),
+        };
+        let info = mock_info("verifies", &[]);
+        execute(deps.as_mut(), mock_env_height(999), info, too_much_msg).unwrap_err();
+
+        // complete release by verifier, before expiration
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
         };
-        let mut env = mock_env();
-        env.block.height = 999;
-        env.block.time = Timestamp::from_seconds(0);
         let info = mock_info("verifies", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, partial_msg).unwrap();
+        let execute_res = execute(deps.as_mut(), mock_env_height(999), info, msg).unwrap();
         assert_eq!(1, execute_res.messages.len());
         let msg = execute_res.messages.get(0).expect("no message");
         assert_eq!(
@@ -277,6 +1251,9 @@ mod tests {
                 amount: coins(500, "earth"),
             })
         );
+
+        // the escrow is closed once everything was released
+        assert_eq!(ESCROWS.may_load(&deps.storage, "foobar").unwrap(), None);
     }
 
     #[test]
@@ -284,59 +1261,529 @@ mod tests {
         let mut deps = mock_dependencies();
 
         // initialize the store
-        let init_amount = coins(1000, "earth");
-        let msg = init_msg_expire_by_height(Some(Expiration::AtHeight(1000)));
-        let mut env = mock_env();
-        env.block.height = 876;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info("creator", &init_amount);
-        let contract_addr = env.clone().contract.address;
-        let init_res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, init_res.messages.len());
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
 
-        // balance changed in init
-        deps.querier.update_balance(&contract_addr, init_amount);
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let create_res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        assert_eq!(0, create_res.messages.len());
 
         // cannot release when unexpired (height < Expiration::AtHeight(1000))
-        let msg = ExecuteMsg::Refund {};
-        let mut env = mock_env();
-        env.block.height = 800;
-        env.block.time = Timestamp::from_seconds(0);
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
         let info = mock_info("anybody", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg);
+        let execute_res = execute(deps.as_mut(), mock_env_height(800), info, msg.clone());
         match execute_res.unwrap_err() {
             ContractError::NotExpired { .. } => {}
             e => panic!("unexpected error: {:?}", e),
         }
 
-        // Contract expires when height == Expiration::AtHeight(1000)
-        let msg = ExecuteMsg::Refund {};
-        let mut env = mock_env();
-        env.block.height = 1000;
-        env.block.time = Timestamp::from_seconds(0);
+        // Escrow expires when height == Expiration::AtHeight(1000), anyone can refund it then
         let info = mock_info("anybody", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg).unwrap();
+        let execute_res = execute(deps.as_mut(), mock_env_height(1000), info, msg.clone()).unwrap();
         assert_eq!(1, execute_res.messages.len());
-        let msg = execute_res.messages.get(0).expect("no message");
+        let refund = execute_res.messages.get(0).expect("no message");
         assert_eq!(
-            msg.msg,
+            refund.msg,
             CosmosMsg::Bank(BankMsg::Send {
                 to_address: "creator".into(),
                 amount: coins(1000, "earth"),
             })
         );
 
-        // anyone can release after expiration
-        let msg = ExecuteMsg::Refund {};
-        let mut env = mock_env();
-        env.block.height = 1001;
-        env.block.time = Timestamp::from_seconds(0);
+        // the escrow is gone, so it cannot be refunded twice
         let info = mock_info("anybody", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(1, execute_res.messages.len());
-        let msg = execute_res.messages.get(0).expect("no message");
+        execute(deps.as_mut(), mock_env_height(1001), info, msg).unwrap_err();
+    }
+
+    #[test]
+    fn cw20_escrow() {
+        let mut deps = mock_dependencies();
+
+        // initialize the store
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        // create an escrow by sending cw20 tokens
+        let create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![String::from("verifies")],
+            threshold: 1,
+            recipient: String::from("benefits"),
+            expiration: Some(Expiration::AtHeight(1000)),
+            auto_release: None,
+            milestones: vec![],
+            cw20_whitelist: vec![],
+        };
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("creator"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Create(create.clone())).unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let details = query_details(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert_eq!(details.source, Addr::unchecked("creator"));
+        assert!(details.balance.native.is_empty());
         assert_eq!(
-            msg.msg,
+            details.balance.cw20,
+            vec![Cw20CoinVerified {
+                address: Addr::unchecked("token"),
+                amount: Uint128::new(100),
+            }]
+        );
+
+        // partial release only moves native tokens, which are not there
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: Some(coins(1, "earth")),
+        };
+        let info = mock_info("verifies", &[]);
+        execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap_err();
+
+        // complete release sends a cw20 transfer to the recipient
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("benefits"),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(ESCROWS.may_load(&deps.storage, "foobar").unwrap(), None);
+
+        // a fresh one can be refunded to the cw20 sender once expired
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("creator"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Create(create)).unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("creator"),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    #[test]
+    fn top_up_and_release_tracked_balance() {
+        let mut deps = mock_dependencies();
+
+        // initialize the store
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Create(CreateMsg {
+            cw20_whitelist: vec![String::from("token")],
+            ..create_msg("foobar", None)
+        });
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // top ups need some tokens and an existing escrow
+        let top_up = ExecuteMsg::TopUp {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(876), info, top_up.clone());
+        match res.unwrap_err() {
+            ContractError::EmptyBalance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let msg = ExecuteMsg::TopUp {
+            id: "unknown".to_string(),
+        };
+        let info = mock_info("anybody", &coins(10, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap_err();
+
+        // native top up merges with the existing coins
+        let info = mock_info("anybody", &[coin(500, "earth"), coin(200, "moon")]);
+        execute(deps.as_mut(), mock_env_height(876), info, top_up).unwrap();
+
+        // anyone can send a Receive message, so only whitelisted cw20 contracts are accepted
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("anybody"),
+            amount: Uint128::new(50),
+            msg: to_binary(&ReceiveMsg::TopUp {
+                id: "foobar".to_string(),
+            })
+            .unwrap(),
+        });
+        let info = mock_info("fake_token", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(876), info, msg);
+        match res.unwrap_err() {
+            ContractError::NotInWhitelist {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // cw20 top ups are tracked per token contract
+        for _ in 0..2 {
+            let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: String::from("anybody"),
+                amount: Uint128::new(50),
+                msg: to_binary(&ReceiveMsg::TopUp {
+                    id: "foobar".to_string(),
+                })
+                .unwrap(),
+            });
+            let info = mock_info("token", &[]);
+            execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        }
+
+        let details = query_details(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert_eq!(
+            details.balance,
+            GenericBalance {
+                native: vec![coin(1500, "earth"), coin(200, "moon")],
+                cw20: vec![Cw20CoinVerified {
+                    address: Addr::unchecked("token"),
+                    amount: Uint128::new(100),
+                }],
+            }
+        );
+
+        // stray funds held by the contract are not swept, only the tracked balance is released
+        deps.querier.update_balance(
+            mock_env().contract.address,
+            vec![coin(9999, "earth"), coin(200, "moon")],
+        );
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: vec![coin(1500, "earth"), coin(200, "moon")],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("benefits"),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+    }
+
+    fn milestone(title: &str, amount: u128, deadline: Option<Expiration>) -> MilestoneMsg {
+        MilestoneMsg {
+            title: title.to_string(),
+            amount: coins(amount, "earth"),
+            cw20_amount: vec![],
+            deadline,
+            arbiter: None,
+        }
+    }
+
+    #[test]
+    fn milestone_releases() {
+        let mut deps = mock_dependencies();
+
+        // initialize the store
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        // milestones need an amount
+        let mut create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![String::from("verifies")],
+            threshold: 1,
+            recipient: String::from("benefits"),
+            expiration: None,
+            auto_release: None,
+            milestones: vec![milestone("design", 0, None)],
+            cw20_whitelist: vec![],
+        };
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info.clone(),
+            ExecuteMsg::Create(create.clone()),
+        );
+        match res.unwrap_err() {
+            ContractError::EmptyMilestone {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let mut build = milestone("build", 600, Some(Expiration::AtHeight(1000)));
+        build.arbiter = Some(String::from("inspector"));
+        create.milestones = vec![milestone("design", 400, None), build];
+        execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            ExecuteMsg::Create(create),
+        )
+        .unwrap();
+
+        // free-form approvals are blocked while milestones are pending
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg);
+        match res.unwrap_err() {
+            ContractError::PendingMilestones {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the first milestone is approved by the escrow arbiter
+        let msg = ExecuteMsg::ApproveMilestone {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(400, "earth"),
+            })
+        );
+
+        let milestones = query_milestones(deps.as_ref(), "foobar".to_string())
+            .unwrap()
+            .milestones;
+        assert!(milestones[0].released);
+        assert!(!milestones[1].released);
+
+        // the second one has its own arbiter
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // and cannot be approved after its deadline
+        let info = mock_info("inspector", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::MilestoneExpired { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the missed deadline lets the source refund the unreleased milestone
+        let refund = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(999),
+            info.clone(),
+            refund.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(600, "earth"),
+            })
+        );
+        assert_eq!(ESCROWS.may_load(&deps.storage, "foobar").unwrap(), None);
+    }
+
+    #[test]
+    fn threshold_approval() {
+        let mut deps = mock_dependencies();
+
+        // initialize the store
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        let mut create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![
+                String::from("alice"),
+                String::from("bob"),
+                String::from("carl"),
+            ],
+            threshold: 4,
+            recipient: String::from("benefits"),
+            expiration: None,
+            auto_release: None,
+            milestones: vec![],
+            cw20_whitelist: vec![],
+        };
+
+        // threshold cannot exceed the number of arbiters, nor be zero
+        for threshold in [0, 4] {
+            create.threshold = threshold;
+            let info = mock_info("creator", &coins(1000, "earth"));
+            let msg = ExecuteMsg::Create(create.clone());
+            let res = execute(deps.as_mut(), mock_env_height(876), info, msg);
+            match res.unwrap_err() {
+                ContractError::InvalidThreshold {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        create.threshold = 2;
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = ExecuteMsg::Create(create.clone());
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // partial releases would need all arbiters to agree on the quantity
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: Some(coins(10, "earth")),
+        };
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg);
+        match res.unwrap_err() {
+            ContractError::PartialRelease {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the first approval is only recorded
+        let approve = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, approve.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // approving twice does not count twice
+        let info = mock_info("alice", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, approve.clone()).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let votes = query_votes(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert_eq!(
+            votes.votes,
+            vec![VoteInfo {
+                arbiter: Addr::unchecked("alice"),
+                vote: Vote::Approve,
+            }]
+        );
+
+        // second approval releases the funds
+        let info = mock_info("carl", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, approve).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(1000, "earth"),
+            })
+        );
+        assert_eq!(ESCROWS.may_load(&deps.storage, "foobar").unwrap(), None);
+        let votes = query_votes(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert!(votes.votes.is_empty());
+
+        // enough rejections allow a refund before expiration
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let msg = ExecuteMsg::Create(create);
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let reject = ExecuteMsg::Reject {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, reject.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info("bob", &[]);
+        execute(deps.as_mut(), mock_env_height(900), info, reject.clone()).unwrap();
+
+        let refund = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, refund.clone());
+        match res.unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let info = mock_info("carl", &[]);
+        execute(deps.as_mut(), mock_env_height(900), info, reject).unwrap();
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
             CosmosMsg::Bank(BankMsg::Send {
                 to_address: "creator".into(),
                 amount: coins(1000, "earth"),
@@ -344,31 +1791,729 @@ mod tests {
         );
     }
 
+    #[test]
+    fn dispute_and_resolve() {
+        let mut deps = mock_dependencies();
+
+        // initialize the store
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // nothing to resolve yet
+        let resolve = ExecuteMsg::Resolve {
+            id: "foobar".to_string(),
+            to_recipient: coins(600, "earth"),
+            to_source: coins(300, "earth"),
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, resolve.clone());
+        match res.unwrap_err() {
+            ContractError::NotDisputed {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // only the parties can raise a dispute, and only once
+        let dispute = ExecuteMsg::Dispute {
+            id: "foobar".to_string(),
+            reason: "work not delivered".to_string(),
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, dispute.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info("benefits", &[]);
+        execute(deps.as_mut(), mock_env_height(900), info, dispute.clone()).unwrap();
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, dispute);
+        match res.unwrap_err() {
+            ContractError::AlreadyDisputed {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let arbiter = query_arbiter(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert_eq!(
+            arbiter.dispute,
+            Some(Dispute {
+                raised_by: Addr::unchecked("benefits"),
+                reason: "work not delivered".to_string(),
+            })
+        );
+
+        // expiration no longer allows a refund
+        let refund = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1001), info, refund.clone());
+        match res.unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the arbiter splits the balance, even after expiration
+        let info = mock_info("benefits", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1001), info, resolve.clone());
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1001), info, resolve).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(600, "earth"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(300, "earth"),
+            })
+        );
+
+        // the rest stays in the escrow, which can be refunded again
+        let details = query_details(deps.as_ref(), "foobar".to_string()).unwrap();
+        assert_eq!(details.dispute, None);
+        assert_eq!(details.balance.native, coins(100, "earth"));
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1001), info, refund).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(100, "earth"),
+            })
+        );
+    }
+
+    #[test]
+    fn arbiter_fees() {
+        let mut deps = mock_dependencies();
+
+        // fees above 100% are rejected
+        let msg = InstantiateMsg {
+            arbiter_fee: Some(ArbiterFee {
+                amount: FeeAmount::BasisPoints(10_001),
+                refund_policy: RefundFeePolicy::Waive,
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        let res = instantiate(deps.as_mut(), mock_env_height(876), info, msg);
+        match res.unwrap_err() {
+            ContractError::InvalidFee {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // 1% fee, shared by the arbiters on release, not taken from refunds
+        let fee = ArbiterFee {
+            amount: FeeAmount::BasisPoints(100),
+            refund_policy: RefundFeePolicy::Waive,
+        };
+        let msg = InstantiateMsg {
+            arbiter_fee: Some(fee.clone()),
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        let config = query_config(deps.as_ref()).unwrap();
+        assert_eq!(config.arbiter_fee, Some(fee));
+
+        let create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![String::from("alice"), String::from("bob")],
+            threshold: 1,
+            recipient: String::from("benefits"),
+            expiration: Some(Expiration::AtHeight(1000)),
+            auto_release: None,
+            milestones: vec![],
+            cw20_whitelist: vec![],
+        };
+        let info = mock_info("creator", &coins(1110, "earth"));
+        let msg = ExecuteMsg::Create(create.clone());
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("bob", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|msg| msg.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "benefits".into(),
+                    amount: coins(1099, "earth"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "alice".into(),
+                    amount: coins(6, "earth"),
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: "bob".into(),
+                    amount: coins(5, "earth"),
+                }),
+            ]
+        );
+        assert!(res.attributes.contains(&attr("payout", "1099earth")));
+        assert!(res.attributes.contains(&attr("arbiter_fee", "11earth")));
+
+        let info = mock_info("creator", &coins(1110, "earth"));
+        let msg = ExecuteMsg::Create(create);
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1110, "earth"),
+            })
+        );
+    }
+
+    #[test]
+    fn flat_arbiter_fee_on_refund() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            arbiter_fee: Some(ArbiterFee {
+                amount: FeeAmount::Flat(coins(50, "earth")),
+                refund_policy: RefundFeePolicy::Charge,
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // refunds are charged as well
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg).unwrap();
+        assert_eq!(2, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(950, "earth"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "verifies".into(),
+                amount: coins(50, "earth"),
+            })
+        );
+    }
+
+    #[test]
+    fn flat_arbiter_fee_is_capped_at_payout() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            arbiter_fee: Some(ArbiterFee {
+                amount: FeeAmount::Flat(coins(100, "earth")),
+                refund_policy: RefundFeePolicy::Charge,
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // a release smaller than the fee pays it all as fee
+        let msg = create_msg_expire_by_height("foobar", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(50, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "verifies".into(),
+                amount: coins(50, "earth"),
+            })
+        );
+        assert!(res.attributes.contains(&attr("arbiter_fee", "50earth")));
+
+        // and so does a refund, which is never blocked by the fee
+        let msg = create_msg_expire_by_height("refunded", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(50, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        let msg = ExecuteMsg::Refund {
+            id: "refunded".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "verifies".into(),
+                amount: coins(50, "earth"),
+            })
+        );
+        query_details(deps.as_ref(), "refunded".to_string()).unwrap_err();
+    }
+
+    #[test]
+    fn flat_arbiter_fee_skips_other_denoms() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            arbiter_fee: Some(ArbiterFee {
+                amount: FeeAmount::Flat(coins(50, "earth")),
+                refund_policy: RefundFeePolicy::Charge,
+            }),
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        // a cw20 only escrow has nothing to pay a native fee with
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("creator"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::Create(create_msg(
+                "foobar",
+                Some(Expiration::AtHeight(1000)),
+            )))
+            .unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("benefits"),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // neither does one held in another denom, even when charged on refunds
+        let msg = create_msg_expire_by_height("moon", Some(Expiration::AtHeight(1000)));
+        let info = mock_info("creator", &coins(1000, "moon"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Refund {
+            id: "moon".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(1000), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1000, "moon"),
+            })
+        );
+    }
+
+    #[test]
+    fn history_records_deposits_and_payouts() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Create(CreateMsg {
+            cw20_whitelist: vec![String::from("token")],
+            ..create_msg("foobar", Some(Expiration::AtHeight(1000)))
+        });
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("anybody"),
+            amount: Uint128::new(50),
+            msg: to_binary(&ReceiveMsg::TopUp {
+                id: "foobar".to_string(),
+            })
+            .unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        execute(deps.as_mut(), mock_env_height(880), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Approve {
+            id: "foobar".to_string(),
+            quantity: Some(coins(300, "earth")),
+        };
+        let info = mock_info("verifies", &[]);
+        execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+
+        // failed attempts are not recorded
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let info = mock_info("anybody", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env_height(950),
+            info.clone(),
+            msg.clone(),
+        )
+        .unwrap_err();
+        execute(deps.as_mut(), mock_env_height(1001), info, msg).unwrap();
+
+        // the history outlives the escrow
+        let history = query_history(deps.as_ref(), None, None).unwrap();
+        let events: Vec<_> = history
+            .entries
+            .iter()
+            .map(|entry| (entry.index, entry.event.clone(), entry.height))
+            .collect();
+        assert_eq!(
+            events,
+            vec![
+                (1, EscrowEvent::Create, 876),
+                (2, EscrowEvent::TopUp, 880),
+                (3, EscrowEvent::Approve, 900),
+                (4, EscrowEvent::Refund, 1001),
+            ]
+        );
+        assert_eq!(
+            history.entries[1],
+            HistoryEntry {
+                index: 2,
+                id: "foobar".to_string(),
+                event: EscrowEvent::TopUp,
+                height: 880,
+                time: Timestamp::from_seconds(0),
+                actor: Addr::unchecked("anybody"),
+                coins: GenericBalance {
+                    native: vec![],
+                    cw20: vec![Cw20CoinVerified {
+                        address: Addr::unchecked("token"),
+                        amount: Uint128::new(50),
+                    }],
+                },
+            }
+        );
+        assert_eq!(history.entries[0].actor, Addr::unchecked("creator"));
+        assert_eq!(history.entries[2].actor, Addr::unchecked("verifies"));
+        assert_eq!(history.entries[2].coins.native, coins(300, "earth"));
+        assert_eq!(history.entries[3].coins.native, coins(700, "earth"));
+
+        // paginates by index
+        let page = query_history(deps.as_ref(), Some(2), Some(1)).unwrap();
+        assert_eq!(page.entries.len(), 1);
+        assert_eq!(page.entries[0].index, 3);
+        let page = query_history(deps.as_ref(), Some(4), None).unwrap();
+        assert!(page.entries.is_empty());
+    }
+
+    #[test]
+    fn claim_after_auto_release() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg {
+                arbiter_fee: Some(ArbiterFee {
+                    amount: FeeAmount::BasisPoints(100),
+                    refund_policy: RefundFeePolicy::Charge,
+                }),
+            },
+        )
+        .unwrap();
+
+        // the auto release has to come before the expiration
+        let mut create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![String::from("verifies")],
+            threshold: 1,
+            recipient: String::from("benefits"),
+            expiration: Some(Expiration::AtHeight(1000)),
+            auto_release: Some(Expiration::AtHeight(1000)),
+            milestones: vec![],
+            cw20_whitelist: vec![],
+        };
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info.clone(),
+            ExecuteMsg::Create(create.clone()),
+        );
+        match res.unwrap_err() {
+            ContractError::InvalidAutoRelease {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        create.auto_release = Some(Expiration::AtHeight(950));
+        execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            ExecuteMsg::Create(create),
+        )
+        .unwrap();
+
+        let claim = ExecuteMsg::Claim {
+            id: "foobar".to_string(),
+        };
+        // too early
+        let info = mock_info("benefits", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(949),
+            info.clone(),
+            claim.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::NotClaimable {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        // only the recipient can claim
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(950),
+            mock_info("verifies", &[]),
+            claim.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        // not once the escrow expired, it can only be refunded then
+        let res = execute(
+            deps.as_mut(),
+            mock_env_height(1000),
+            info.clone(),
+            claim.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Expired { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        // neither can the source get a refund in between
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
+        let res = execute(deps.as_mut(), mock_env_height(950), info.clone(), msg);
+        match res.unwrap_err() {
+            ContractError::NotExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the whole balance goes to the recipient, without arbiter fee
+        let res = execute(deps.as_mut(), mock_env_height(950), info, claim).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(1000, "earth"),
+            })
+        );
+        assert_eq!(res.attributes[0], attr("action", "claim"));
+        query_details(deps.as_ref(), "foobar".to_string()).unwrap_err();
+
+        let history = query_history(deps.as_ref(), Some(1), None).unwrap();
+        assert_eq!(history.entries[0].event, EscrowEvent::Claim);
+        assert_eq!(history.entries[0].coins.native, coins(1000, "earth"));
+    }
+
+    #[test]
+    fn disputed_escrow_cannot_be_claimed() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
+
+        let create = CreateMsg {
+            id: "foobar".to_string(),
+            arbiters: vec![String::from("verifies")],
+            threshold: 1,
+            recipient: String::from("benefits"),
+            expiration: None,
+            auto_release: Some(Expiration::AtTime(Timestamp::from_seconds(100))),
+            milestones: vec![],
+            cw20_whitelist: vec![],
+        };
+        let info = mock_info("creator", &coins(1000, "earth"));
+        execute(
+            deps.as_mut(),
+            mock_env_height(876),
+            info.clone(),
+            ExecuteMsg::Create(create),
+        )
+        .unwrap();
+        let msg = ExecuteMsg::Dispute {
+            id: "foobar".to_string(),
+            reason: "never delivered".to_string(),
+        };
+        execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+
+        let mut env = mock_env_height(900);
+        env.block.time = Timestamp::from_seconds(100);
+        let msg = ExecuteMsg::Claim {
+            id: "foobar".to_string(),
+        };
+        let res = execute(deps.as_mut(), env, mock_info("benefits", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Disputed {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn migrate_legacy_escrow() {
+        let mut deps = mock_dependencies();
+
+        // state as stored by the single escrow contract
+        set_contract_version(&mut deps.storage, LEGACY_CONTRACT_NAME, "0.11.0").unwrap();
+        let legacy = LegacyConfig {
+            arbiter: Addr::unchecked("verifies"),
+            recipient: Addr::unchecked("benefits"),
+            source: Addr::unchecked("creator"),
+            expiration: Some(Expiration::AtHeight(1000)),
+        };
+        LEGACY_CONFIG.save(&mut deps.storage, &legacy).unwrap();
+        deps.querier
+            .update_balance(mock_env().contract.address, coins(1000, "earth"));
+
+        let res = migrate(deps.as_mut(), mock_env_height(900), MigrateMsg {}).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let version = get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.contract, CONTRACT_NAME);
+        assert_eq!(version.version, CONTRACT_VERSION);
+        assert_eq!(query_config(deps.as_ref()).unwrap().arbiter_fee, None);
+
+        // the old escrow and its funds are available under the legacy id
+        let details = query_details(deps.as_ref(), LEGACY_ESCROW_ID.to_string()).unwrap();
+        assert_eq!(details.arbiters, vec![Addr::unchecked("verifies")]);
+        assert_eq!(details.threshold, 1);
+        assert_eq!(details.recipient, Addr::unchecked("benefits"));
+        assert_eq!(details.source, Addr::unchecked("creator"));
+        assert_eq!(details.balance.native, coins(1000, "earth"));
+
+        let msg = ExecuteMsg::Approve {
+            id: LEGACY_ESCROW_ID.to_string(),
+            quantity: None,
+        };
+        let info = mock_info("verifies", &[]);
+        let res = execute(deps.as_mut(), mock_env_height(900), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "benefits".into(),
+                amount: coins(1000, "earth"),
+            })
+        );
+
+        // migrating again keeps the current state
+        migrate(deps.as_mut(), mock_env_height(900), MigrateMsg {}).unwrap();
+        assert_eq!(query_config(deps.as_ref()).unwrap().arbiter_fee, None);
+    }
+
+    #[test]
+    fn migrate_checks_version() {
+        let mut deps = mock_dependencies();
+
+        set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.11.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res.unwrap_err() {
+            ContractError::CannotMigrate { previous_contract } => {
+                assert_eq!(previous_contract, "crates.io:cw20-base")
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        set_contract_version(&mut deps.storage, CONTRACT_NAME, "99.0.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res.unwrap_err() {
+            ContractError::CannotMigrateVersion { previous_version } => {
+                assert_eq!(previous_version, "99.0.0")
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
     #[test]
     fn handle_refund_no_expiration() {
         let mut deps = mock_dependencies();
 
         // initialize the store
-        let init_amount = coins(1000, "earth");
-        let msg = init_msg_expire_by_height(None);
-        let mut env = mock_env();
-        env.block.height = 876;
-        env.block.time = Timestamp::from_seconds(0);
-        let info = mock_info("creator", &init_amount);
-        let contract_addr = env.clone().contract.address;
-        let init_res = instantiate(deps.as_mut(), env, info, msg).unwrap();
-        assert_eq!(0, init_res.messages.len());
+        let info = mock_info("creator", &[]);
+        instantiate(
+            deps.as_mut(),
+            mock_env_height(876),
+            info,
+            InstantiateMsg { arbiter_fee: None },
+        )
+        .unwrap();
 
-        // balance changed in init
-        deps.querier.update_balance(&contract_addr, init_amount);
+        let msg = create_msg_expire_by_height("foobar", None);
+        let info = mock_info("creator", &coins(1000, "earth"));
+        let create_res = execute(deps.as_mut(), mock_env_height(876), info, msg).unwrap();
+        assert_eq!(0, create_res.messages.len());
 
         // cannot release when unexpired (no expiration)
-        let msg = ExecuteMsg::Refund {};
-        let mut env = mock_env();
-        env.block.height = 800;
-        env.block.time = Timestamp::from_seconds(0);
+        let msg = ExecuteMsg::Refund {
+            id: "foobar".to_string(),
+        };
         let info = mock_info("anybody", &[]);
-        let execute_res = execute(deps.as_mut(), env, info, msg);
+        let execute_res = execute(deps.as_mut(), mock_env_height(800), info, msg);
         match execute_res.unwrap_err() {
             ContractError::NotExpired { .. } => {}
             e => panic!("unexpected error: {:?}", e),
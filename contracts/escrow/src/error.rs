@@ -7,6 +7,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("Semver parsing error: {0}")]
+    SemVer(String),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -15,4 +18,61 @@ pub enum ContractError {
 
     #[error("Escrow not expired")]
     NotExpired {},
+
+    #[error("Auto release must come before the escrow expiration")]
+    InvalidAutoRelease {},
+
+    #[error("Escrow cannot be claimed before its auto release")]
+    NotClaimable {},
+
+    #[error("Escrow id already in use")]
+    AlreadyInUse {},
+
+    #[error("Send some coins to create an escrow")]
+    EmptyBalance {},
+
+    #[error("Only accepts tokens in the cw20_whitelist")]
+    NotInWhitelist {},
+
+    #[error("Milestone amount cannot be empty")]
+    EmptyMilestone {},
+
+    #[error("Escrow has pending milestones, approve them one at a time")]
+    PendingMilestones {},
+
+    #[error("All milestones were released already")]
+    NoPendingMilestone {},
+
+    #[error("Milestone deadline passed (deadline: {deadline:?})")]
+    MilestoneExpired { deadline: Expiration },
+
+    #[error("Threshold must be between 1 and the number of distinct arbiters")]
+    InvalidThreshold {},
+
+    #[error("Partial releases are only supported with a threshold of 1")]
+    PartialRelease {},
+
+    #[error("Escrow is already disputed")]
+    AlreadyDisputed {},
+
+    #[error("Escrow is not disputed")]
+    NotDisputed {},
+
+    #[error("Escrow is disputed, it has to be resolved by the arbiters")]
+    Disputed {},
+
+    #[error("Arbiter fee cannot exceed 10000 basis points")]
+    InvalidFee {},
+
+    #[error("Cannot migrate from a different contract: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Cannot migrate from a newer version: {previous_version}")]
+    CannotMigrateVersion { previous_version: String },
+}
+
+impl From<semver::Error> for ContractError {
+    fn from(err: semver::Error) -> Self {
+        Self::SemVer(err.to_string())
+    }
 }
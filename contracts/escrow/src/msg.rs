@@ -1,10 +1,91 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::{Addr, Coin};
+use cw20::{Cw20Coin, Cw20ReceiveMsg};
 use cw_utils::Expiration;
 
+use crate::state::{ArbiterFee, Dispute, GenericBalance, HistoryEntry, Milestone, Vote};
+
 #[cw_serde]
 pub struct InstantiateMsg {
-    pub arbiter: String,
+    /// Optional fee paid to the arbiters whenever an escrow pays out
+    pub arbiter_fee: Option<ArbiterFee>,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Creates a new escrow holding the native tokens sent along with this message.
+    /// The sender is recorded as the source and gets the tokens back on refund.
+    Create(CreateMsg),
+    /// Releases tokens of an escrow without milestones, or of one whose milestones were all
+    /// released already. With several arbiters this records an approval vote, and the tokens
+    /// are released once `threshold` arbiters approved.
+    Approve {
+        id: String,
+        // release some native coins - if quantity is None, release all coins and cw20 tokens
+        // in balance. Partial releases are only supported with a threshold of 1.
+        quantity: Option<Vec<Coin>>,
+    },
+    /// Releases the amount of the next pending milestone to the recipient.
+    /// Only callable by the arbiter of that milestone, before its deadline.
+    /// Milestones without an own arbiter are voted on like `Approve`.
+    ApproveMilestone {
+        id: String,
+    },
+    /// Records a rejection vote of an arbiter. Once enough arbiters rejected the escrow
+    /// that the threshold can no longer be reached, it can be refunded before expiration.
+    Reject {
+        id: String,
+    },
+    Refund {
+        id: String,
+    },
+    /// Raises a dispute, only callable by the source or the recipient. While disputed,
+    /// the escrow can no longer be refunded because it expired.
+    Dispute {
+        id: String,
+        reason: String,
+    },
+    /// Settles a dispute by splitting native tokens between the parties, anything left stays
+    /// in the escrow. With several arbiters, `threshold` of them have to vote for the same split.
+    Resolve {
+        id: String,
+        to_recipient: Vec<Coin>,
+        to_source: Vec<Coin>,
+    },
+    /// Adds the native tokens sent along with this message to an existing escrow.
+    /// They are paid out like the rest of the escrow, so a refund returns them to the source.
+    TopUp {
+        id: String,
+    },
+    /// Releases all remaining tokens to the recipient once the auto release of the escrow
+    /// passed, only callable by the recipient and only before the escrow expires.
+    Claim {
+        id: String,
+    },
+    /// Receive forwards received cw20 tokens to an execution logic
+    Receive(Cw20ReceiveMsg),
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Creates a new escrow holding the received cw20 tokens.
+    /// The cw20 sender is recorded as the source and gets the tokens back on refund.
+    Create(CreateMsg),
+    /// Adds the received cw20 tokens to an existing escrow.
+    /// Only accepted from token contracts in the `cw20_whitelist` of the escrow.
+    TopUp { id: String },
+}
+
+#[cw_serde]
+pub struct CreateMsg {
+    /// id is a unique, human-readable name for the escrow
+    pub id: String,
+    pub arbiters: Vec<String>,
+    /// Number of arbiters that have to approve a release
+    pub threshold: u32,
     pub recipient: String,
     /// When end height set and block height exceeds this value, the escrow is expired.
     /// Once an escrow is expired, it can be returned to the original funder (via "refund").
@@ -13,26 +94,115 @@ pub struct InstantiateMsg {
     /// block time exceeds this value, the escrow is expired.
     /// Once an escrow is expired, it can be returned to the original funder (via "refund").
     pub expiration: Option<Expiration>,
+    /// Once reached, the recipient can `Claim` the tokens without approval of the arbiters,
+    /// as long as the escrow is not expired yet. Has to come before the expiration.
+    pub auto_release: Option<Expiration>,
+    /// Optional payment plan, milestones are approved in the given order
+    #[serde(default)]
+    pub milestones: Vec<MilestoneMsg>,
+    /// cw20 token contracts accepted by `TopUp`. The token an escrow is created with and
+    /// the tokens of its milestones are accepted as well.
+    #[serde(default)]
+    pub cw20_whitelist: Vec<String>,
 }
 
 #[cw_serde]
-pub enum ExecuteMsg {
-    Approve {
-        // release some coins - if quantity is None, release all coins in balance
-        quantity: Option<Vec<Coin>>,
-    },
-    Refund {},
+pub struct MilestoneMsg {
+    pub title: String,
+    pub amount: Vec<Coin>,
+    #[serde(default)]
+    pub cw20_amount: Vec<Cw20Coin>,
+    /// Once reached, the milestone can no longer be approved and the escrow can be refunded
+    pub deadline: Option<Expiration>,
+    /// Arbiter for this milestone only, defaults to the escrow arbiters
+    pub arbiter: Option<String>,
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    /// Returns a human-readable representation of the arbiter.
+    /// Returns the contract wide configuration, like the arbiter fee.
+    #[returns(ConfigResponse)]
+    Config {},
+    /// Returns the arbiters of the given escrow and how many of them need to approve.
     #[returns(ArbiterResponse)]
-    Arbiter {},
+    Arbiter { id: String },
+    /// Returns the votes cast on the next release of the given escrow.
+    #[returns(VotesResponse)]
+    Votes { id: String },
+    /// Lists the ids of all open escrows, ordered by id.
+    #[returns(ListResponse)]
+    List {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Returns the full state of the given escrow.
+    #[returns(DetailsResponse)]
+    Details { id: String },
+    /// Returns the milestones of the given escrow and which of them were released.
+    #[returns(MilestonesResponse)]
+    Milestones { id: String },
+    /// Lists deposits and payouts of all escrows, oldest first. Entries are kept
+    /// after an escrow is closed.
+    #[returns(HistoryResponse)]
+    History {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct ConfigResponse {
+    pub arbiter_fee: Option<ArbiterFee>,
 }
 
 #[cw_serde]
 pub struct ArbiterResponse {
+    pub arbiters: Vec<Addr>,
+    pub threshold: u32,
+    /// Dispute the arbiters have to resolve, if any
+    pub dispute: Option<Dispute>,
+}
+
+#[cw_serde]
+pub struct VotesResponse {
+    pub votes: Vec<VoteInfo>,
+}
+
+#[cw_serde]
+pub struct VoteInfo {
     pub arbiter: Addr,
+    pub vote: Vote,
+}
+
+#[cw_serde]
+pub struct ListResponse {
+    /// ids of the open escrows
+    pub escrows: Vec<String>,
+}
+
+#[cw_serde]
+pub struct DetailsResponse {
+    pub id: String,
+    pub arbiters: Vec<Addr>,
+    pub threshold: u32,
+    pub recipient: Addr,
+    pub source: Addr,
+    pub expiration: Option<Expiration>,
+    pub auto_release: Option<Expiration>,
+    /// Tokens still held by the escrow
+    pub balance: GenericBalance,
+    pub dispute: Option<Dispute>,
+    /// cw20 token contracts accepted by `TopUp`
+    pub cw20_whitelist: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct MilestonesResponse {
+    pub milestones: Vec<Milestone>,
+}
+
+#[cw_serde]
+pub struct HistoryResponse {
+    pub entries: Vec<HistoryEntry>,
 }
@@ -1,15 +1,225 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Addr;
-use cw_storage_plus::Item;
-use cw_utils::Expiration;
+use cosmwasm_std::{Addr, Coin, OverflowError, OverflowOperation, StdError, StdResult, Timestamp};
+use cw20::{Balance, Cw20CoinVerified};
+use cw_storage_plus::{Item, Map};
+use cw_utils::{Expiration, NativeBalance};
+use std::fmt;
 
 #[cw_serde]
 pub struct Config {
+    /// Paid to the arbiters out of every settlement, if set
+    pub arbiter_fee: Option<ArbiterFee>,
+}
+
+#[cw_serde]
+pub struct ArbiterFee {
+    pub amount: FeeAmount,
+    pub refund_policy: RefundFeePolicy,
+}
+
+#[cw_serde]
+pub enum FeeAmount {
+    /// Fixed native amount taken from every payout holding the same denom, at most all of it.
+    /// Payouts without that denom, like cw20 only ones, are not charged.
+    Flat(Vec<Coin>),
+    /// Share of every payout, in 1/10000
+    BasisPoints(u64),
+}
+
+#[cw_serde]
+pub enum RefundFeePolicy {
+    /// Refunds are returned in full, arbiters are only paid when funds are released
+    Waive,
+    /// The fee is taken from refunds as well
+    Charge,
+}
+
+#[cw_serde]
+pub struct Escrow {
+    /// Releases need approval of `threshold` of these arbiters
+    pub arbiters: Vec<Addr>,
+    pub threshold: u32,
+    pub recipient: Addr,
+    pub source: Addr,
+    pub expiration: Option<Expiration>,
+    /// Once passed without the escrow being refunded, the recipient can claim the tokens
+    /// until the escrow expires, even if the arbiters never approved
+    pub auto_release: Option<Expiration>,
+    /// Tokens deposited for this escrow that have not been released yet.
+    /// Only these are paid out on approve or refund.
+    pub balance: GenericBalance,
+    /// Ordered payment plan. When set, tokens are released one milestone at a time
+    /// instead of via a free-form approve.
+    pub milestones: Vec<Milestone>,
+    /// Set while a party disputes the escrow, until the arbiters resolve it
+    pub dispute: Option<Dispute>,
+    /// cw20 token contracts that can be topped up. Anyone can send a `Receive` message,
+    /// so tokens from other contracts could be fake and block every payout.
+    pub cw20_whitelist: Vec<Addr>,
+}
+
+impl Escrow {
+    /// Returns the first milestone that was not released yet, with its index
+    pub fn next_milestone(&self) -> Option<(usize, &Milestone)> {
+        self.milestones
+            .iter()
+            .enumerate()
+            .find(|(_, milestone)| !milestone.released)
+    }
+
+    /// Number of rejections after which the approval threshold can no longer be reached
+    pub fn rejection_threshold(&self) -> u32 {
+        self.arbiters.len() as u32 - self.threshold + 1
+    }
+
+    /// An escrow is closed once all tokens and milestones are paid out
+    pub fn is_closed(&self) -> bool {
+        self.balance.is_empty() && self.next_milestone().is_none()
+    }
+}
+
+#[cw_serde]
+pub struct Dispute {
+    pub raised_by: Addr,
+    pub reason: String,
+}
+
+#[cw_serde]
+pub struct Milestone {
+    pub title: String,
+    pub amount: GenericBalance,
+    /// The milestone can no longer be approved once the deadline is reached.
+    pub deadline: Option<Expiration>,
+    /// Arbiter for this milestone only, falls back to the escrow arbiters if not set
+    pub arbiter: Option<Addr>,
+    pub released: bool,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub struct GenericBalance {
+    pub native: Vec<Coin>,
+    /// one entry per cw20 token contract
+    pub cw20: Vec<Cw20CoinVerified>,
+}
+
+impl GenericBalance {
+    pub fn is_empty(&self) -> bool {
+        self.native.iter().all(|coin| coin.amount.is_zero())
+            && self.cw20.iter().all(|token| token.is_empty())
+    }
+
+    pub fn add_tokens(&mut self, add: Balance) {
+        match add {
+            Balance::Native(balance) => {
+                let mut native = NativeBalance(std::mem::take(&mut self.native));
+                native += balance;
+                native.normalize();
+                self.native = native.into_vec();
+            }
+            Balance::Cw20(token) => {
+                match self.cw20.iter_mut().find(|t| t.address == token.address) {
+                    Some(existing) => existing.amount += token.amount,
+                    None => self.cw20.push(token),
+                }
+            }
+        }
+    }
+
+    /// Removes the given tokens, erroring if this balance does not hold enough of them
+    pub fn sub_tokens(&mut self, sub: &GenericBalance) -> StdResult<()> {
+        let native = NativeBalance(std::mem::take(&mut self.native)) - sub.native.clone();
+        self.native = native?.into_vec();
+
+        for token in &sub.cw20 {
+            match self.cw20.iter().position(|t| t.address == token.address) {
+                Some(idx) => {
+                    let remainder = self.cw20[idx].amount.checked_sub(token.amount)?;
+                    if remainder.is_zero() {
+                        self.cw20.remove(idx);
+                    } else {
+                        self.cw20[idx].amount = remainder;
+                    }
+                }
+                None => {
+                    return Err(StdError::overflow(OverflowError::new(
+                        OverflowOperation::Sub,
+                        0,
+                        token.amount.u128(),
+                    )))
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for GenericBalance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let native = self.native.iter().map(|coin| coin.to_string());
+        let cw20 = self
+            .cw20
+            .iter()
+            .map(|token| format!("{}{}", token.amount, token.address));
+        write!(f, "{}", native.chain(cw20).collect::<Vec<_>>().join(","))
+    }
+}
+
+#[cw_serde]
+pub enum Vote {
+    Approve,
+    Reject,
+    /// Settles a dispute by paying out the given amounts. Only identical splits
+    /// count towards the threshold.
+    Resolve {
+        to_recipient: Vec<Coin>,
+        to_source: Vec<Coin>,
+    },
+}
+
+/// Audit trail entry, written whenever tokens enter or leave an escrow
+#[cw_serde]
+pub struct HistoryEntry {
+    /// Position in the history, increasing with every entry
+    pub index: u64,
+    /// id of the escrow the tokens were deposited to or paid out of
+    pub id: String,
+    pub event: EscrowEvent,
+    pub height: u64,
+    pub time: Timestamp,
+    /// Sender of the message that caused this event
+    pub actor: Addr,
+    /// Tokens deposited or paid out, before any arbiter fee is deducted
+    pub coins: GenericBalance,
+}
+
+#[cw_serde]
+pub enum EscrowEvent {
+    Create,
+    TopUp,
+    /// Tokens were released to the recipient, via `Approve` or `ApproveMilestone`
+    Approve,
+    Refund,
+    Resolve,
+    /// Tokens were claimed by the recipient after the auto release passed
+    Claim,
+}
+
+/// Layout of the single escrow stored by versions before 0.12, only read when migrating
+#[cw_serde]
+pub struct LegacyConfig {
     pub arbiter: Addr,
     pub recipient: Addr,
     pub source: Addr,
     pub expiration: Option<Expiration>,
 }
 
-pub const CONFIG_KEY: &str = "config";
-pub const CONFIG: Item<Config> = Item::new(CONFIG_KEY);
+pub const LEGACY_CONFIG: Item<LegacyConfig> = Item::new("config");
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const ESCROWS: Map<&str, Escrow> = Map::new("escrow");
+/// Votes of the escrow arbiters on the next release, keyed by escrow id and arbiter.
+/// They are cleared whenever tokens are paid out.
+pub const VOTES: Map<(&str, &Addr), Vote> = Map::new("votes");
+/// Events of all escrows, keyed by their index. Entries are never removed.
+pub const HISTORY: Map<u64, HistoryEntry> = Map::new("history");
+pub const HISTORY_COUNT: Item<u64> = Item::new("history_count");
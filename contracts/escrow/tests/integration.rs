@@ -21,12 +21,12 @@ use cosmwasm_std::{
     coins, Addr, BlockInfo, Coin, ContractInfo, Env, MessageInfo, Response, Timestamp,
     TransactionInfo,
 };
-use cosmwasm_vm::testing::{instantiate, mock_info, mock_instance};
-use cosmwasm_vm::{from_slice, Storage};
+use cosmwasm_vm::from_slice;
+use cosmwasm_vm::testing::{execute, instantiate, mock_info, mock_instance, query};
 
 use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
-use cw_escrow::msg::InstantiateMsg;
-use cw_escrow::state::Config;
+use cw_escrow::msg::{CreateMsg, DetailsResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
+use cw_escrow::state::GenericBalance;
 use cw_utils::Expiration;
 
 // This line will test the output of cargo wasm
@@ -34,12 +34,17 @@ static WASM: &[u8] = include_bytes!("../target/wasm32-unknown-unknown/release/cw
 // You can uncomment this line instead to test productionified build from rust-optimizer
 // static WASM: &[u8] = include_bytes!("../contract.wasm");
 
-fn init_msg_expire_by_height(expiration: Expiration) -> InstantiateMsg {
-    InstantiateMsg {
-        arbiter: String::from("verifies"),
+fn create_msg_expire_by_height(id: &str, expiration: Expiration) -> ExecuteMsg {
+    ExecuteMsg::Create(CreateMsg {
+        id: id.to_string(),
+        arbiters: vec![String::from("verifies")],
+        threshold: 1,
         recipient: String::from("benefits"),
         expiration: Some(expiration),
-    }
+        auto_release: None,
+        milestones: vec![],
+        cw20_whitelist: vec![],
+    })
 }
 
 fn mock_env_info_height(signer: &str, sent: &[Coin], height: u64, time: u64) -> (Env, MessageInfo) {
@@ -61,24 +66,39 @@ fn mock_env_info_height(signer: &str, sent: &[Coin], height: u64, time: u64) ->
 #[test]
 fn proper_initialization() {
     let mut deps = mock_instance(WASM, &[]);
-    let msg = init_msg_expire_by_height(Expiration::AtHeight(1000));
+    let (env, info) = mock_env_info_height("creator", &[], 876, 0);
+    let res: Response =
+        instantiate(&mut deps, env, info, InstantiateMsg { arbiter_fee: None }).unwrap();
+    assert_eq!(0, res.messages.len());
+
+    let msg = create_msg_expire_by_height("foobar", Expiration::AtHeight(1000));
     let (env, info) = mock_env_info_height("creator", &coins(1000, "earth"), 876, 0);
-    let res: Response = instantiate(&mut deps, env, info, msg).unwrap();
+    let res: Response = execute(&mut deps, env, info, msg).unwrap();
     assert_eq!(0, res.messages.len());
 
     // it worked, let's query the state
-    deps.with_storage(|store| {
-        let state: Config = from_slice(&store.get(b"config").0.unwrap().unwrap(), 2048).unwrap();
-        assert_eq!(
-            state,
-            Config {
-                arbiter: Addr::unchecked("verifies"),
-                recipient: Addr::unchecked("benefits"),
-                source: Addr::unchecked("creator"),
-                expiration: Some(Expiration::AtHeight(1000))
-            }
-        );
-        Ok(())
-    })
-    .unwrap();
+    let (env, _) = mock_env_info_height("anyone", &[], 876, 0);
+    let msg = QueryMsg::Details {
+        id: "foobar".to_string(),
+    };
+    let res = query(&mut deps, env, msg).unwrap();
+    let details: DetailsResponse = from_slice(&res, 2048).unwrap();
+    assert_eq!(
+        details,
+        DetailsResponse {
+            id: "foobar".to_string(),
+            arbiters: vec![Addr::unchecked("verifies")],
+            threshold: 1,
+            recipient: Addr::unchecked("benefits"),
+            source: Addr::unchecked("creator"),
+            expiration: Some(Expiration::AtHeight(1000)),
+            auto_release: None,
+            balance: GenericBalance {
+                native: coins(1000, "earth"),
+                cw20: vec![],
+            },
+            dispute: None,
+            cw20_whitelist: vec![],
+        }
+    );
 }
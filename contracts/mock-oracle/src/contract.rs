@@ -0,0 +1,116 @@
+use cosmwasm_std::{
+    entry_point, to_binary, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult,
+};
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, PriceResponse, QueryMsg};
+use crate::state::{OWNER, PRICES};
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    OWNER.save(deps.storage, &info.sender)?;
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetPrice { base, quote, price } => {
+            execute_set_price(deps, info, base, quote, price)
+        }
+    }
+}
+
+pub fn execute_set_price(
+    deps: DepsMut,
+    info: MessageInfo,
+    base: String,
+    quote: String,
+    price: Decimal,
+) -> Result<Response, ContractError> {
+    if info.sender != OWNER.load(deps.storage)? {
+        return Err(ContractError::Unauthorized {});
+    }
+    PRICES.save(deps.storage, (base.as_str(), quote.as_str()), &price)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "set_price"),
+        ("base", base.as_str()),
+        ("quote", quote.as_str()),
+        ("price", price.to_string().as_str()),
+    ]);
+    Ok(res)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Price { base, quote } => to_binary(&query_price(deps, base, quote)?),
+    }
+}
+
+fn query_price(deps: Deps, base: String, quote: String) -> StdResult<PriceResponse> {
+    let price = PRICES
+        .may_load(deps.storage, (base.as_str(), quote.as_str()))?
+        .ok_or_else(|| StdError::not_found(format!("price of {} in {}", base, quote)))?;
+    Ok(PriceResponse { price })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+
+    #[test]
+    fn set_and_query_price() {
+        let mut deps = mock_dependencies();
+
+        let info = mock_info("owner", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, InstantiateMsg {}).unwrap();
+
+        // unknown pairs are not found
+        let err = query_price(deps.as_ref(), "BTC".to_string(), "USD".to_string()).unwrap_err();
+        assert!(matches!(err, StdError::NotFound { .. }));
+
+        // only the owner can set prices
+        let info = mock_info("anyone", &[]);
+        let err = execute_set_price(
+            deps.as_mut(),
+            info,
+            "BTC".to_string(),
+            "USD".to_string(),
+            Decimal::percent(150),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let info = mock_info("owner", &[]);
+        execute_set_price(
+            deps.as_mut(),
+            info,
+            "BTC".to_string(),
+            "USD".to_string(),
+            Decimal::percent(150),
+        )
+        .unwrap();
+        let res = query_price(deps.as_ref(), "BTC".to_string(), "USD".to_string()).unwrap();
+        assert_eq!(Decimal::percent(150), res.price);
+
+        // pairs are directed
+        let _ = query_price(deps.as_ref(), "USD".to_string(), "BTC".to_string()).unwrap_err();
+    }
+}
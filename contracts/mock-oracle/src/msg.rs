@@ -0,0 +1,28 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Decimal;
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Owner sets the price of one `base` unit in `quote` units
+    SetPrice {
+        base: String,
+        quote: String,
+        price: Decimal,
+    },
+}
+
+/// The price oracle interface queried by cash settled simple-options
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(PriceResponse)]
+    Price { base: String, quote: String },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal,
+}
@@ -0,0 +1,8 @@
+use cosmwasm_std::{Addr, Decimal};
+use cw_storage_plus::{Item, Map};
+
+/// Only the owner can set prices
+pub const OWNER: Item<Addr> = Item::new("owner");
+
+/// Price of one base unit in quote units, keyed by (base, quote)
+pub const PRICES: Map<(&str, &str), Decimal> = Map::new("prices");
@@ -1,5 +1,6 @@
 use crate::error::ContractError;
 use cosmwasm_std::Coin;
+use cw_utils::NativeBalance;
 
 pub fn assert_sent_sufficient_coin(
     sent: &[Coin],
@@ -24,6 +25,26 @@ pub fn assert_sent_sufficient_coin(
     Ok(())
 }
 
+/// take_fee checks that the sent coins cover the required fee,
+/// and splits them into the fee and the change to refund to the sender
+pub fn take_fee(
+    sent: &[Coin],
+    required: Option<Coin>,
+) -> Result<(Vec<Coin>, Vec<Coin>), ContractError> {
+    assert_sent_sufficient_coin(sent, required.clone())?;
+
+    let mut change = NativeBalance(sent.to_vec());
+    change.normalize();
+    let fee = match required {
+        Some(required_coin) if !required_coin.amount.is_zero() => {
+            change = (change - required_coin.clone())?;
+            vec![required_coin]
+        }
+        _ => vec![],
+    };
+    Ok((fee, change.into_vec()))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -59,4 +80,32 @@ mod test {
             Err(e) => panic!("Unexpected error: {:?}", e),
         };
     }
+
+    #[test]
+    fn take_fee_refunds_change() {
+        let (fee, change) = take_fee(&[], None).unwrap();
+        assert_eq!((fee, change), (vec![], vec![]));
+
+        // everything is change if there is no fee
+        let (fee, change) = take_fee(&coins(3, "token"), Some(coin(0, "token"))).unwrap();
+        assert_eq!((fee, change), (vec![], coins(3, "token")));
+
+        let (fee, change) = take_fee(&coins(5, "token"), Some(coin(5, "token"))).unwrap();
+        assert_eq!((fee, change), (coins(5, "token"), vec![]));
+
+        // overpayments and other denoms are refunded
+        let sent_coins = vec![coin(2, "smokin"), coin(7, "token"), coin(1, "earth")];
+        let (fee, change) = take_fee(&sent_coins, Some(coin(5, "token"))).unwrap();
+        assert_eq!(fee, coins(5, "token"));
+        assert_eq!(
+            change,
+            vec![coin(1, "earth"), coin(2, "smokin"), coin(2, "token")]
+        );
+
+        match take_fee(&coins(4, "token"), Some(coin(5, "token"))) {
+            Ok(_) => panic!("Should have raised insufficient funds error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        };
+    }
 }
@@ -1,27 +1,61 @@
 use cosmwasm_std::{
-    entry_point, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult,
+    coin, entry_point, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, Deps, DepsMut, Empty,
+    Env, MessageInfo, Order, Response, StdError, StdResult, Storage, Uint128,
 };
+use cw_storage_plus::Bound;
+use cw_utils::{must_pay, Duration, Expiration, NativeBalance};
 
-use crate::coin_helpers::assert_sent_sufficient_coin;
+use crate::coin_helpers::take_fee;
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg, ResolveRecordResponse};
-use crate::state::{Config, NameRecord, CONFIG, NAME_RESOLVER};
+use crate::msg::{
+    AuctionResponse, ConfigResponse, ExecuteMsg, FeesCollectedResponse, InstantiateMsg,
+    NamesResponse, QueryMsg, RecordsResponse, ResolveRecordResponse, ReverseRecordResponse,
+    SubdomainsResponse,
+};
+use crate::name_helpers::{canonical_name, name_length, normalize_name};
+use crate::state::{
+    name_resolver, Auction, AuctionConfig, Config, Fees, LengthPrice, NameRecord, AUCTIONS, CONFIG,
+    FEES, PRIMARY_NAMES, SUBDOMAINS,
+};
 
 const MIN_NAME_LENGTH: u64 = 3;
-const MAX_NAME_LENGTH: u64 = 64;
+const MIN_LABEL_LENGTH: u64 = 1;
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
-    _info: MessageInfo,
+    info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, StdError> {
+    // the grace period is added to expirations set by the registration period
+    match (msg.registration_period, msg.grace_period) {
+        (Duration::Height(_), Duration::Height(_)) | (Duration::Time(_), Duration::Time(_)) => {}
+        _ => {
+            return Err(StdError::generic_err(
+                "registration and grace period must both be heights or times",
+            ))
+        }
+    }
+
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
     let config = Config {
+        admin,
         purchase_price: msg.purchase_price,
+        length_prices: msg.length_prices,
         transfer_price: msg.transfer_price,
+        registration_period: msg.registration_period,
+        grace_period: msg.grace_period,
+        auction: msg.auction,
     };
     CONFIG.save(deps.storage, &config)?;
+    FEES.save(deps.storage, &Fees::default())?;
 
     Ok(Response::default())
 }
@@ -36,109 +70,692 @@ pub fn execute(
     match msg {
         ExecuteMsg::Register { name } => execute_register(deps, env, info, name),
         ExecuteMsg::Transfer { name, to } => execute_transfer(deps, env, info, name, to),
+        ExecuteMsg::Renew { name } => execute_renew(deps, env, info, name),
+        ExecuteMsg::SetAddress { name, address } => {
+            execute_set_address(deps, env, info, name, address)
+        }
+        ExecuteMsg::SetRecord { name, key, value } => {
+            execute_set_record(deps, env, info, name, key, value)
+        }
+        ExecuteMsg::RegisterSubdomain {
+            parent,
+            label,
+            owner,
+        } => execute_register_subdomain(deps, env, info, parent, label, owner),
+        ExecuteMsg::TransferSubdomain { parent, label, to } => {
+            execute_transfer_subdomain(deps, env, info, parent, label, to)
+        }
+        ExecuteMsg::RevokeSubdomain { parent, label } => {
+            execute_revoke_subdomain(deps, env, info, parent, label)
+        }
+        ExecuteMsg::SetPrimaryName { name } => execute_set_primary_name(deps, env, info, name),
+        ExecuteMsg::Bid { name } => execute_bid(deps, env, info, name),
+        ExecuteMsg::SettleAuction { name } => execute_settle_auction(deps, env, name),
+        ExecuteMsg::UpdateConfig {
+            admin,
+            purchase_price,
+            length_prices,
+            transfer_price,
+            auction,
+        } => execute_update_config(
+            deps,
+            info,
+            admin,
+            purchase_price,
+            length_prices,
+            transfer_price,
+            auction,
+        ),
+        ExecuteMsg::WithdrawFees { to, amount } => execute_withdraw_fees(deps, info, to, amount),
     }
 }
 
 pub fn execute_register(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     name: String,
 ) -> Result<Response, ContractError> {
     // we only need to check here - at point of registration
-    validate_name(&name)?;
+    let name = normalize_name(&name, MIN_NAME_LENGTH)?;
     let config = CONFIG.load(deps.storage)?;
-    assert_sent_sufficient_coin(&info.funds, config.purchase_price)?;
+    if is_auctioned(&config, &name) || AUCTIONS.has(deps.storage, &name) {
+        return Err(ContractError::AuctionRequired { name });
+    }
+    let res = charge(deps.storage, &info, price_for(&config, &name))?;
+
+    save_registration(deps.storage, &env.block, &config, &name, info.sender)?;
+
+    Ok(res)
+}
+
+/// charge records the fee paid by the sender and refunds any overpayment
+fn charge(
+    storage: &mut dyn Storage,
+    info: &MessageInfo,
+    price: Option<Coin>,
+) -> Result<Response, ContractError> {
+    let (fee, change) = take_fee(&info.funds, price)?;
+    if !fee.is_empty() {
+        add_fees(storage, fee)?;
+    }
 
+    let mut res = Response::new();
+    if !change.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: change,
+        });
+    }
+    Ok(res)
+}
+
+fn add_fees(storage: &mut dyn Storage, fee: Vec<Coin>) -> StdResult<()> {
+    let mut fees = FEES.may_load(storage)?.unwrap_or_default();
+    fees.collected += NativeBalance(fee.clone());
+    fees.available += NativeBalance(fee);
+    FEES.save(storage, &fees)
+}
+
+/// save_registration registers an available name to owner for one registration period
+fn save_registration(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    config: &Config,
+    name: &str,
+    owner: Addr,
+) -> Result<(), ContractError> {
     let key = name.as_bytes();
-    let record = NameRecord { owner: info.sender };
+    let record = NameRecord {
+        owner,
+        expires: config.registration_period.after(block),
+        address: None,
+        records: Default::default(),
+    };
 
-    if (NAME_RESOLVER.may_load(deps.storage, key)?).is_some() {
-        // name is already taken
-        return Err(ContractError::NameTaken { name });
+    if let Some(existing) = name_resolver().may_load(storage, key)? {
+        // name is taken until its grace period ends
+        if !is_released(&existing, config, block)? {
+            return Err(ContractError::NameTaken {
+                name: name.to_string(),
+            });
+        }
+        // subdomains were given out by the prior owner
+        remove_subdomains(storage, name)?;
+        clear_primary_name(storage, &existing.owner, name)?;
     }
 
     // name is available
-    NAME_RESOLVER.save(deps.storage, key, &record)?;
+    name_resolver().save(storage, key, &record)?;
+    Ok(())
+}
+
+/// price_for returns the price to register or renew a name,
+/// from the lowest length tier the name fits in, or the purchase_price
+fn price_for(config: &Config, name: &str) -> Option<Coin> {
+    let length = name_length(name);
+    config
+        .length_prices
+        .iter()
+        .filter(|tier| length <= tier.max_length)
+        .min_by_key(|tier| tier.max_length)
+        .map(|tier| tier.price.clone())
+        .or_else(|| config.purchase_price.clone())
+}
+
+/// is_auctioned returns true if the name is too short to be registered without an auction
+fn is_auctioned(config: &Config, name: &str) -> bool {
+    match &config.auction {
+        Some(auction) => name_length(name) <= auction.max_length,
+        None => false,
+    }
+}
+
+pub fn execute_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let name = normalize_name(&name, MIN_NAME_LENGTH)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let mut res = Response::new();
+    let auction = match AUCTIONS.may_load(deps.storage, &name)? {
+        Some(auction) => {
+            if auction.ends.is_expired(&env.block) {
+                return Err(ContractError::AuctionEnded { name });
+            }
+            // bids must top the highest bid in its denom
+            let amount = must_pay(&info, &auction.bid.denom)?;
+            if amount <= auction.bid.amount {
+                return Err(ContractError::BidTooLow {
+                    bid: amount,
+                    min_bid: auction.bid.amount + Uint128::new(1),
+                });
+            }
+            res = res.add_message(BankMsg::Send {
+                to_address: auction.bidder.into(),
+                amount: vec![auction.bid.clone()],
+            });
+            Auction {
+                bidder: info.sender,
+                bid: coin(amount.u128(), auction.bid.denom),
+                ends: auction.ends,
+            }
+        }
+        None => {
+            let auction_config = match &config.auction {
+                Some(auction_config) if is_auctioned(&config, &name) => auction_config,
+                _ => return Err(ContractError::NotAuctioned { name }),
+            };
+            if let Some(existing) = name_resolver().may_load(deps.storage, name.as_bytes())? {
+                if !is_released(&existing, &config, &env.block)? {
+                    return Err(ContractError::NameTaken { name });
+                }
+            }
+            let amount = must_pay(&info, &auction_config.min_bid.denom)?;
+            if amount < auction_config.min_bid.amount {
+                return Err(ContractError::BidTooLow {
+                    bid: amount,
+                    min_bid: auction_config.min_bid.amount,
+                });
+            }
+            // the first bid starts the auction
+            Auction {
+                bidder: info.sender,
+                bid: coin(amount.u128(), &auction_config.min_bid.denom),
+                ends: auction_config.duration.after(&env.block),
+            }
+        }
+    };
+    AUCTIONS.save(deps.storage, &name, &auction)?;
+
+    Ok(res)
+}
+
+pub fn execute_settle_auction(
+    deps: DepsMut,
+    env: Env,
+    name: String,
+) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
+    let auction = AUCTIONS
+        .may_load(deps.storage, &name)?
+        .ok_or_else(|| ContractError::AuctionNotFound { name: name.clone() })?;
+    if !auction.ends.is_expired(&env.block) {
+        return Err(ContractError::AuctionNotEnded { name });
+    }
+
+    // the contract keeps the winning bid as the price of the name
+    let config = CONFIG.load(deps.storage)?;
+    save_registration(deps.storage, &env.block, &config, &name, auction.bidder)?;
+    AUCTIONS.remove(deps.storage, &name);
+    add_fees(deps.storage, vec![auction.bid])?;
 
     Ok(Response::default())
 }
 
+pub fn execute_update_config(
+    deps: DepsMut,
+    info: MessageInfo,
+    admin: Option<String>,
+    purchase_price: Option<Coin>,
+    length_prices: Vec<LengthPrice>,
+    transfer_price: Option<Coin>,
+    auction: Option<AuctionConfig>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    if let Some(admin) = admin {
+        config.admin = deps.api.addr_validate(&admin)?;
+    }
+    config.purchase_price = purchase_price;
+    config.length_prices = length_prices;
+    config.transfer_price = transfer_price;
+    config.auction = auction;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::default())
+}
+
+pub fn execute_withdraw_fees(
+    deps: DepsMut,
+    info: MessageInfo,
+    to: String,
+    amount: Vec<Coin>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let to = deps.api.addr_validate(&to)?;
+
+    let mut amount = NativeBalance(amount);
+    amount.normalize();
+    if amount.is_empty() {
+        return Err(ContractError::EmptyWithdrawal {});
+    }
+    let amount = amount.into_vec();
+
+    // outstanding bids are held by the contract too, only fees can be withdrawn
+    let mut fees = FEES.may_load(deps.storage)?.unwrap_or_default();
+    fees.available =
+        (fees.available - amount.clone()).map_err(|_| ContractError::InsufficientFees {})?;
+    FEES.save(deps.storage, &fees)?;
+
+    Ok(Response::new().add_message(BankMsg::Send {
+        to_address: to.into(),
+        amount,
+    }))
+}
+
+pub fn execute_renew(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
+    if name.contains('.') {
+        return Err(ContractError::NotTopLevelName { name });
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let res = charge(deps.storage, &info, price_for(&config, &name))?;
+
+    let key = name.as_bytes();
+    let mut record = name_resolver()
+        .may_load(deps.storage, key)?
+        .ok_or_else(|| ContractError::NameNotExists { name: name.clone() })?;
+
+    if record.expires.is_expired(&env.block) {
+        if is_released(&record, &config, &env.block)? {
+            return Err(ContractError::NameExpired { name });
+        }
+        // within the grace period
+        if info.sender != record.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+    }
+
+    // renewals extend the current registration, even if renewed late
+    record.expires = (record.expires + config.registration_period)?;
+    name_resolver().save(deps.storage, key, &record)?;
+
+    Ok(res)
+}
+
+/// is_released returns true once the grace period after the expiry of a name ended,
+/// so anyone can register it again
+fn is_released(record: &NameRecord, config: &Config, block: &BlockInfo) -> StdResult<bool> {
+    let grace_end = (record.expires + config.grace_period)?;
+    Ok(grace_end.is_expired(block))
+}
+
 pub fn execute_transfer(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     name: String,
     to: String,
 ) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
     let config = CONFIG.load(deps.storage)?;
-    assert_sent_sufficient_coin(&info.funds, config.transfer_price)?;
+    let res = charge(deps.storage, &info, config.transfer_price)?;
 
     let new_owner = deps.api.addr_validate(&to)?;
-    let key = name.as_bytes();
-    NAME_RESOLVER.update(deps.storage, key, |record| {
-        if let Some(mut record) = record {
-            if info.sender != record.owner {
-                return Err(ContractError::Unauthorized {});
-            }
+    let mut record = load_owned_record(deps.as_ref(), &env, &info, &name)?;
+    record.owner = new_owner;
+    name_resolver().save(deps.storage, name.as_bytes(), &record)?;
+    clear_primary_name(deps.storage, &info.sender, &name)?;
+
+    Ok(res)
+}
+
+pub fn execute_register_subdomain(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: String,
+    label: String,
+    owner: String,
+) -> Result<Response, ContractError> {
+    let parent = canonical_name(&parent);
+    let label = normalize_name(&label, MIN_LABEL_LENGTH)?;
+    load_owned_record(deps.as_ref(), &env, &info, &parent)?;
+
+    let name = format!("{}.{}", label, parent);
+    if name_resolver().has(deps.storage, name.as_bytes()) {
+        return Err(ContractError::NameTaken { name });
+    }
+
+    let record = NameRecord {
+        owner: deps.api.addr_validate(&owner)?,
+        expires: Expiration::Never {},
+        address: None,
+        records: Default::default(),
+    };
+    name_resolver().save(deps.storage, name.as_bytes(), &record)?;
+    SUBDOMAINS.save(deps.storage, (parent.as_str(), label.as_str()), &Empty {})?;
+
+    Ok(Response::default())
+}
+
+pub fn execute_transfer_subdomain(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: String,
+    label: String,
+    to: String,
+) -> Result<Response, ContractError> {
+    let parent = canonical_name(&parent);
+    let label = canonical_name(&label);
+    load_owned_record(deps.as_ref(), &env, &info, &parent)?;
+
+    let new_owner = deps.api.addr_validate(&to)?;
+    let name = format!("{}.{}", label, parent);
+    let mut record = name_resolver()
+        .may_load(deps.storage, name.as_bytes())?
+        .ok_or_else(|| ContractError::NameNotExists { name: name.clone() })?;
+    clear_primary_name(deps.storage, &record.owner, &name)?;
+    record.owner = new_owner;
+    name_resolver().save(deps.storage, name.as_bytes(), &record)?;
+
+    Ok(Response::default())
+}
+
+pub fn execute_revoke_subdomain(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    parent: String,
+    label: String,
+) -> Result<Response, ContractError> {
+    let parent = canonical_name(&parent);
+    let label = canonical_name(&label);
+    load_owned_record(deps.as_ref(), &env, &info, &parent)?;
+
+    let name = format!("{}.{}", label, parent);
+    if !name_resolver().has(deps.storage, name.as_bytes()) {
+        return Err(ContractError::NameNotExists { name });
+    }
+    remove_name(deps.storage, &name)?;
+    SUBDOMAINS.remove(deps.storage, (parent.as_str(), label.as_str()));
+
+    Ok(Response::default())
+}
+
+/// remove_subdomains deletes all subdomains of a name, recursively
+fn remove_subdomains(storage: &mut dyn Storage, name: &str) -> StdResult<()> {
+    let labels = SUBDOMAINS
+        .prefix(name)
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for label in labels {
+        remove_name(storage, &format!("{}.{}", label, name))?;
+        SUBDOMAINS.remove(storage, (name, label.as_str()));
+    }
+    Ok(())
+}
+
+/// remove_name deletes a name and all of its subdomains
+fn remove_name(storage: &mut dyn Storage, name: &str) -> StdResult<()> {
+    remove_subdomains(storage, name)?;
+    if let Some(record) = name_resolver().may_load(storage, name.as_bytes())? {
+        clear_primary_name(storage, &record.owner, name)?;
+        name_resolver().remove(storage, name.as_bytes())?;
+    }
+    Ok(())
+}
+
+pub fn execute_set_primary_name(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
+    load_owned_record(deps.as_ref(), &env, &info, &name)?;
+    PRIMARY_NAMES.save(deps.storage, &info.sender, &name)?;
+
+    Ok(Response::default())
+}
+
+/// clear_primary_name removes the primary name of the prior owner of a name
+fn clear_primary_name(storage: &mut dyn Storage, owner: &Addr, name: &str) -> StdResult<()> {
+    if PRIMARY_NAMES.may_load(storage, owner)?.as_deref() == Some(name) {
+        PRIMARY_NAMES.remove(storage, owner);
+    }
+    Ok(())
+}
+
+pub fn execute_set_address(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    address: String,
+) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
+    let mut record = load_owned_record(deps.as_ref(), &env, &info, &name)?;
+    record.address = Some(deps.api.addr_validate(&address)?);
+    name_resolver().save(deps.storage, name.as_bytes(), &record)?;
 
-            record.owner = new_owner.clone();
-            Ok(record)
-        } else {
-            Err(ContractError::NameNotExists { name: name.clone() })
-        }
-    })?;
     Ok(Response::default())
 }
 
+pub fn execute_set_record(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    name: String,
+    key: String,
+    value: String,
+) -> Result<Response, ContractError> {
+    let name = canonical_name(&name);
+    let mut record = load_owned_record(deps.as_ref(), &env, &info, &name)?;
+    if value.is_empty() {
+        record.records.remove(&key);
+    } else {
+        record.records.insert(key, value);
+    }
+    name_resolver().save(deps.storage, name.as_bytes(), &record)?;
+
+    Ok(Response::default())
+}
+
+/// load_owned_record returns the record of a name, if it is owned by the sender and not expired
+fn load_owned_record(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    name: &str,
+) -> Result<NameRecord, ContractError> {
+    let record = name_resolver()
+        .may_load(deps.storage, name.as_bytes())?
+        .ok_or_else(|| ContractError::NameNotExists {
+            name: name.to_string(),
+        })?;
+    if info.sender != record.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if active_record(deps.storage, &env.block, name)?.is_none() {
+        return Err(ContractError::NameExpired {
+            name: name.to_string(),
+        });
+    }
+    Ok(record)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::ResolveRecord { name } => query_resolver(deps, env, name),
+        QueryMsg::Records { name } => to_binary(&query_records(deps, env, name)?),
+        QueryMsg::Subdomains {
+            parent,
+            start_after,
+            limit,
+        } => to_binary(&query_subdomains(deps, parent, start_after, limit)?),
+        QueryMsg::ReverseRecord { address } => {
+            to_binary(&query_reverse_record(deps, env, address)?)
+        }
+        QueryMsg::NamesByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_names_by_owner(deps, env, owner, start_after, limit)?),
+        QueryMsg::Auction { name } => to_binary(&query_auction(deps, name)?),
         QueryMsg::Config {} => to_binary::<ConfigResponse>(&CONFIG.load(deps.storage)?.into()),
+        QueryMsg::FeesCollected {} => to_binary(&query_fees_collected(deps)?),
     }
 }
 
-fn query_resolver(deps: Deps, _env: Env, name: String) -> StdResult<Binary> {
-    let key = name.as_bytes();
-
-    let address = match NAME_RESOLVER.may_load(deps.storage, key)? {
-        Some(record) => Some(String::from(&record.owner)),
-        None => None,
+/// active_record walks the hierarchy of a name and returns its record
+/// if the name and all of its parents exist and the top-level name has not expired
+fn active_record(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    name: &str,
+) -> StdResult<Option<NameRecord>> {
+    let record = match name_resolver().may_load(storage, name.as_bytes())? {
+        Some(record) => record,
+        None => return Ok(None),
     };
+
+    let mut expires = record.expires;
+    let mut current = name;
+    while let Some((_, parent)) = current.split_once('.') {
+        match name_resolver().may_load(storage, parent.as_bytes())? {
+            Some(parent_record) => expires = parent_record.expires,
+            None => return Ok(None),
+        }
+        current = parent;
+    }
+
+    if expires.is_expired(block) {
+        return Ok(None);
+    }
+    Ok(Some(record))
+}
+
+fn query_resolver(deps: Deps, env: Env, name: String) -> StdResult<Binary> {
+    let name = canonical_name(&name);
+    let address = active_record(deps.storage, &env.block, &name)?
+        .map(|record| String::from(record.address.as_ref().unwrap_or(&record.owner)));
     let resp = ResolveRecordResponse { address };
 
     to_binary(&resp)
 }
 
-// let's not import a regexp library and just do these checks by hand
-fn invalid_char(c: char) -> bool {
-    let is_valid =
-        c.is_ascii_digit() || c.is_ascii_lowercase() || (c == '.' || c == '-' || c == '_');
-    !is_valid
-}
-
-/// validate_name returns an error if the name is invalid
-/// (we require 3-64 lowercase ascii letters, numbers, or . - _)
-fn validate_name(name: &str) -> Result<(), ContractError> {
-    let length = name.len() as u64;
-    if (name.len() as u64) < MIN_NAME_LENGTH {
-        Err(ContractError::NameTooShort {
-            length,
-            min_length: MIN_NAME_LENGTH,
-        })
-    } else if (name.len() as u64) > MAX_NAME_LENGTH {
-        Err(ContractError::NameTooLong {
-            length,
-            max_length: MAX_NAME_LENGTH,
-        })
-    } else {
-        match name.find(invalid_char) {
-            None => Ok(()),
-            Some(bytepos_invalid_char_start) => {
-                let c = name[bytepos_invalid_char_start..].chars().next().unwrap();
-                Err(ContractError::InvalidCharacter { c })
+fn query_records(deps: Deps, env: Env, name: String) -> StdResult<RecordsResponse> {
+    let name = canonical_name(&name);
+    let resp = match active_record(deps.storage, &env.block, &name)? {
+        Some(record) => RecordsResponse {
+            address: Some(String::from(
+                record.address.as_ref().unwrap_or(&record.owner),
+            )),
+            owner: Some(record.owner.into()),
+            records: record.records,
+        },
+        None => RecordsResponse {
+            owner: None,
+            address: None,
+            records: Default::default(),
+        },
+    };
+    Ok(resp)
+}
+
+fn query_subdomains(
+    deps: Deps,
+    parent: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SubdomainsResponse> {
+    let parent = canonical_name(&parent);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.as_deref().map(canonical_name);
+    let start = start_after.as_deref().map(Bound::exclusive);
+    let labels = SUBDOMAINS
+        .prefix(&parent)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(SubdomainsResponse { labels })
+}
+
+fn query_reverse_record(deps: Deps, env: Env, address: String) -> StdResult<ReverseRecordResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let name = match PRIMARY_NAMES.may_load(deps.storage, &address)? {
+        // expired names are not cleared, so check the name is still active
+        Some(name) => active_record(deps.storage, &env.block, &name)?
+            .filter(|record| record.owner == address)
+            .map(|_| name),
+        None => None,
+    };
+    Ok(ReverseRecordResponse { name })
+}
+
+fn query_names_by_owner(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<NamesResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after.as_deref().map(canonical_name);
+    let start = start_after
+        .as_ref()
+        .map(|name| Bound::exclusive(name.as_bytes()));
+    let keys =
+        name_resolver()
+            .idx
+            .owner
+            .prefix(owner)
+            .keys(deps.storage, start, None, Order::Ascending);
+
+    // expired names stay indexed until they are registered again
+    let mut names = vec![];
+    for key in keys {
+        let name = String::from_utf8(key?)?;
+        if active_record(deps.storage, &env.block, &name)?.is_some() {
+            names.push(name);
+            if names.len() == limit {
+                break;
             }
         }
     }
+    Ok(NamesResponse { names })
+}
+
+fn query_fees_collected(deps: Deps) -> StdResult<FeesCollectedResponse> {
+    let fees = FEES.may_load(deps.storage)?.unwrap_or_default();
+    Ok(FeesCollectedResponse {
+        collected: fees.collected.into_vec(),
+        available: fees.available.into_vec(),
+    })
+}
+
+fn query_auction(deps: Deps, name: String) -> StdResult<AuctionResponse> {
+    let name = canonical_name(&name);
+    let resp = match AUCTIONS.may_load(deps.storage, &name)? {
+        Some(auction) => AuctionResponse {
+            bidder: Some(auction.bidder.into()),
+            bid: Some(auction.bid),
+            ends: Some(auction.ends),
+        },
+        None => AuctionResponse {
+            bidder: None,
+            bid: None,
+            ends: None,
+        },
+    };
+    Ok(resp)
 }
@@ -1,4 +1,5 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,15 +7,30 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Insufficient funds sent")]
     InsufficientFundsSend {},
 
+    #[error("Withdrawal exceeds the available fees")]
+    InsufficientFees {},
+
+    #[error("Nothing to withdraw")]
+    EmptyWithdrawal {},
+
     #[error("Name does not exist (name {name})")]
     NameNotExists { name: String },
 
+    #[error("Name has expired (name {name})")]
+    NameExpired { name: String },
+
+    #[error("Only top-level names can be renewed (name {name})")]
+    NotTopLevelName { name: String },
+
     #[error("Name has been taken (name {name})")]
     NameTaken { name: String },
 
@@ -24,6 +40,32 @@ pub enum ContractError {
     #[error("Name too long (length {length} min_length {max_length})")]
     NameTooLong { length: u64, max_length: u64 },
 
-    #[error("Invalid character(char {c}")]
-    InvalidCharacter { c: char },
+    #[error("Name is sold by auction (name {name})")]
+    AuctionRequired { name: String },
+
+    #[error("Name is not sold by auction (name {name})")]
+    NotAuctioned { name: String },
+
+    #[error("No auction for name (name {name})")]
+    AuctionNotFound { name: String },
+
+    #[error("Auction has ended (name {name})")]
+    AuctionEnded { name: String },
+
+    #[error("Auction has not ended yet (name {name})")]
+    AuctionNotEnded { name: String },
+
+    #[error("Bid too low (bid {bid} min_bid {min_bid})")]
+    BidTooLow { bid: Uint128, min_bid: Uint128 },
+
+    #[error("Invalid character (char {c:?} code point {code_point} position {position})")]
+    InvalidCharacter {
+        c: char,
+        code_point: String,
+        /// Counted in characters of the name as sent, before it was normalized
+        position: u64,
+    },
+
+    #[error("Name is confusable with another name (name {name})")]
+    ConfusableName { name: String },
 }
@@ -2,6 +2,7 @@ pub mod coin_helpers;
 pub mod contract;
 mod error;
 pub mod msg;
+pub mod name_helpers;
 pub mod state;
 
 #[cfg(test)]
@@ -1,27 +1,132 @@
-use crate::state::Config;
+use crate::state::{AuctionConfig, Config, LengthPrice};
+use std::collections::BTreeMap;
+
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cosmwasm_std::Coin;
+use cw_utils::{Duration, Expiration};
 
 #[cw_serde]
 pub struct InstantiateMsg {
+    /// The admin can update the config and withdraw fees, the sender if not set
+    #[serde(default)]
+    pub admin: Option<String>,
     pub purchase_price: Option<Coin>,
+    #[serde(default)]
+    pub length_prices: Vec<LengthPrice>,
     pub transfer_price: Option<Coin>,
+    pub registration_period: Duration,
+    pub grace_period: Duration,
+    #[serde(default)]
+    pub auction: Option<AuctionConfig>,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    Register { name: String },
-    Transfer { name: String, to: String },
+    Register {
+        name: String,
+    },
+    Transfer {
+        name: String,
+        to: String,
+    },
+    // Renew extends the registration by one period for the price of the name,
+    // only the owner can renew during the grace period after expiry
+    Renew {
+        name: String,
+    },
+    // SetAddress points the name at another address, without transferring it
+    SetAddress {
+        name: String,
+        address: String,
+    },
+    // SetRecord sets a text record of the name, an empty value removes it
+    SetRecord {
+        name: String,
+        key: String,
+        value: String,
+    },
+    // RegisterSubdomain lets the owner of parent give out "label.parent" for free
+    RegisterSubdomain {
+        parent: String,
+        label: String,
+        owner: String,
+    },
+    // TransferSubdomain lets the owner of parent take over and reassign a subdomain
+    TransferSubdomain {
+        parent: String,
+        label: String,
+        to: String,
+    },
+    // RevokeSubdomain lets the owner of parent delete a subdomain and all of its subdomains
+    RevokeSubdomain {
+        parent: String,
+        label: String,
+    },
+    // SetPrimaryName sets the name the sender is known by, it must own the name.
+    // It is cleared when the name is transferred.
+    SetPrimaryName {
+        name: String,
+    },
+    // Bid on a name that is sold by auction, the first bid starts the auction.
+    // The prior highest bid is refunded.
+    Bid {
+        name: String,
+    },
+    // SettleAuction registers the name to the highest bidder once the auction ended
+    SettleAuction {
+        name: String,
+    },
+    // UpdateConfig replaces the pricing, only the admin can update it.
+    // If admin is set, the admin role is handed over.
+    UpdateConfig {
+        admin: Option<String>,
+        purchase_price: Option<Coin>,
+        length_prices: Vec<LengthPrice>,
+        transfer_price: Option<Coin>,
+        auction: Option<AuctionConfig>,
+    },
+    // WithdrawFees lets the admin send collected fees to an address
+    WithdrawFees {
+        to: String,
+        amount: Vec<Coin>,
+    },
 }
 
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    // ResolveAddress returns the current address that the name resolves to
+    // ResolveAddress returns the current address that the name resolves to,
+    // or none if the name is not registered or expired
     #[returns(ResolveRecordResponse)]
     ResolveRecord { name: String },
+    // Records returns the owner, resolved address and text records of the name
+    #[returns(RecordsResponse)]
+    Records { name: String },
+    // Subdomains returns the labels of the subdomains of parent
+    #[returns(SubdomainsResponse)]
+    Subdomains {
+        parent: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // ReverseRecord returns the primary name of an address
+    #[returns(ReverseRecordResponse)]
+    ReverseRecord { address: String },
+    // NamesByOwner returns the names and subdomains owned by an address
+    #[returns(NamesResponse)]
+    NamesByOwner {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    // Auction returns the highest bid on a name, if an auction is running or not yet settled
+    #[returns(AuctionResponse)]
+    Auction { name: String },
     #[returns(ConfigResponse)]
     Config {},
+    // FeesCollected returns all fees collected and those not withdrawn yet
+    #[returns(FeesCollectedResponse)]
+    FeesCollected {},
 }
 
 // We define a custom struct for each query response
@@ -30,17 +135,62 @@ pub struct ResolveRecordResponse {
     pub address: Option<String>,
 }
 
+#[cw_serde]
+pub struct RecordsResponse {
+    pub owner: Option<String>,
+    pub address: Option<String>,
+    pub records: BTreeMap<String, String>,
+}
+
+#[cw_serde]
+pub struct SubdomainsResponse {
+    pub labels: Vec<String>,
+}
+
+#[cw_serde]
+pub struct ReverseRecordResponse {
+    pub name: Option<String>,
+}
+
+#[cw_serde]
+pub struct NamesResponse {
+    pub names: Vec<String>,
+}
+
+#[cw_serde]
+pub struct AuctionResponse {
+    pub bidder: Option<String>,
+    pub bid: Option<Coin>,
+    pub ends: Option<Expiration>,
+}
+
 #[cw_serde]
 pub struct ConfigResponse {
+    pub admin: String,
     pub purchase_price: Option<Coin>,
+    pub length_prices: Vec<LengthPrice>,
     pub transfer_price: Option<Coin>,
+    pub registration_period: Duration,
+    pub grace_period: Duration,
+    pub auction: Option<AuctionConfig>,
+}
+
+#[cw_serde]
+pub struct FeesCollectedResponse {
+    pub collected: Vec<Coin>,
+    pub available: Vec<Coin>,
 }
 
 impl From<Config> for ConfigResponse {
     fn from(config: Config) -> ConfigResponse {
         ConfigResponse {
+            admin: config.admin.into(),
             purchase_price: config.purchase_price,
+            length_prices: config.length_prices,
             transfer_price: config.transfer_price,
+            registration_period: config.registration_period,
+            grace_period: config.grace_period,
+            auction: config.auction,
         }
     }
 }
@@ -0,0 +1,173 @@
+use crate::error::ContractError;
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{skeleton, GeneralSecurityProfile, MixedScript};
+
+pub const MAX_NAME_LENGTH: u64 = 64;
+
+/// canonical_name case-folds a name and composes it to NFC.
+/// Names are stored in this form, so every lookup has to use it too.
+pub fn canonical_name(name: &str) -> String {
+    name.chars().flat_map(char::to_lowercase).nfc().collect()
+}
+
+/// name_length counts the characters of a name, not its bytes
+pub fn name_length(name: &str) -> u64 {
+    name.chars().count() as u64
+}
+
+// ascii is limited to lowercase letters, numbers, - and _,
+// other characters must be allowed in identifiers by UTS #39
+fn invalid_char(c: char) -> bool {
+    let is_valid = if c.is_ascii() {
+        c.is_ascii_digit() || c.is_ascii_lowercase() || (c == '-' || c == '_')
+    } else {
+        c.identifier_allowed()
+    };
+    !is_valid
+}
+
+/// is_confusable returns true if a non-ascii name mixes scripts, like a cyrillic "а" in "pаypal",
+/// or looks like an ascii name, like the all cyrillic "аре"
+fn is_confusable(name: &str) -> bool {
+    if name.is_ascii() {
+        return false;
+    }
+    !name.is_single_script() || skeleton(name).all(|c| c.is_ascii())
+}
+
+/// normalize_name returns the canonical form of a name or subdomain label,
+/// or an error if it is invalid
+/// (we require min_length-64 characters, lowercase ascii letters, numbers, - _
+/// or letters of a single other script, dots only separate subdomains)
+pub fn normalize_name(name: &str, min_length: u64) -> Result<String, ContractError> {
+    let canonical = canonical_name(name);
+    let length = name_length(&canonical);
+    if length < min_length {
+        return Err(ContractError::NameTooShort { length, min_length });
+    }
+    if length > MAX_NAME_LENGTH {
+        return Err(ContractError::NameTooLong {
+            length,
+            max_length: MAX_NAME_LENGTH,
+        });
+    }
+
+    if let Some((position, c)) = canonical
+        .chars()
+        .enumerate()
+        .find(|(_, c)| invalid_char(*c))
+    {
+        return Err(ContractError::InvalidCharacter {
+            c,
+            code_point: format!("U+{:04X}", c as u32),
+            position: original_position(name, position as u64),
+        });
+    }
+    if is_confusable(&canonical) {
+        return Err(ContractError::ConfusableName { name: canonical });
+    }
+    Ok(canonical)
+}
+
+/// original_position maps the position of a character in the canonical form of a name
+/// to the position of the character it came from in the name as sent
+fn original_position(name: &str, canonical_position: u64) -> u64 {
+    // the first character whose prefix reaches the canonical position produced it
+    name.char_indices()
+        .map(|(i, c)| &name[..i + c.len_utf8()])
+        .position(|prefix| name_length(&canonical_name(prefix)) > canonical_position)
+        .map_or(name_length(name), |position| position as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn canonical_name_folds_case_and_composes() {
+        assert_eq!(canonical_name("Alice"), "alice");
+        assert_eq!(canonical_name("ÇAFÉ"), "çafé");
+        // "e" followed by a combining acute accent becomes "é"
+        assert_eq!(canonical_name("cafe\u{301}"), "caf\u{e9}");
+        assert_eq!(canonical_name("Team.Company"), "team.company");
+    }
+
+    #[test]
+    fn name_length_counts_chars() {
+        assert_eq!(name_length("alice"), 5);
+        assert_eq!(name_length("日本"), 2);
+        assert_eq!(name_length("мир"), 3);
+    }
+
+    #[test]
+    fn normalize_name_works() {
+        assert_eq!(normalize_name("ALICE", 3).unwrap(), "alice");
+        assert_eq!(normalize_name("Café", 3).unwrap(), "café");
+        assert_eq!(normalize_name("мир", 3).unwrap(), "мир");
+        assert_eq!(normalize_name("日本", 1).unwrap(), "日本");
+
+        // lengths are counted in characters
+        match normalize_name("日本", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameTooShort { length, .. }) => assert_eq!(length, 2),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        match normalize_name("two words", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InvalidCharacter {
+                c,
+                code_point,
+                position,
+            }) => {
+                assert_eq!(c, ' ');
+                assert_eq!(code_point, "U+0020");
+                assert_eq!(position, 3);
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        match normalize_name("héllo😀", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InvalidCharacter {
+                code_point,
+                position,
+                ..
+            }) => {
+                assert_eq!(code_point, "U+1F600");
+                assert_eq!(position, 5);
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        // positions count the characters of the name as sent, not of its canonical form
+        match normalize_name("cafe\u{301} bar", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InvalidCharacter { c, position, .. }) => {
+                assert_eq!(c, ' ');
+                assert_eq!(position, 5);
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn normalize_name_rejects_homographs() {
+        // latin "p" and "ypal" with a cyrillic "а"
+        match normalize_name("p\u{430}ypal", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::ConfusableName { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        // all cyrillic, but looks like "ape"
+        match normalize_name("\u{430}\u{440}\u{435}", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::ConfusableName { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        // greek omicron in an otherwise latin name
+        match normalize_name("g\u{3bf}\u{3bf}gle", 3) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::ConfusableName { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+}
@@ -1,17 +1,107 @@
+use std::collections::BTreeMap;
+
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Coin};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Coin, Empty};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+use cw_utils::{Duration, Expiration, NativeBalance};
 
 #[cw_serde]
 pub struct Config {
+    /// The only address that can update the config
+    pub admin: Addr,
     pub purchase_price: Option<Coin>,
+    /// Prices of names by length, names longer than all tiers cost the purchase_price
+    pub length_prices: Vec<LengthPrice>,
     pub transfer_price: Option<Coin>,
+    /// How long a registration or renewal lasts
+    pub registration_period: Duration,
+    /// How long after expiry only the prior owner may renew the name
+    pub grace_period: Duration,
+    /// Short names that can only be registered by auction, none if not set
+    pub auction: Option<AuctionConfig>,
+}
+
+/// LengthPrice sets the price of names up to max_length characters,
+/// the tier with the lowest matching max_length applies
+#[cw_serde]
+pub struct LengthPrice {
+    pub max_length: u64,
+    pub price: Coin,
+}
+
+#[cw_serde]
+pub struct AuctionConfig {
+    /// Names up to this length are sold by auction
+    pub max_length: u64,
+    /// How long an auction runs after the first bid
+    pub duration: Duration,
+    /// The lowest first bid, later bids must be in the same denom
+    pub min_bid: Coin,
+}
+
+/// Auction holds the highest bid on a name, which the contract keeps until it is outbid
+#[cw_serde]
+pub struct Auction {
+    pub bidder: Addr,
+    pub bid: Coin,
+    pub ends: Expiration,
 }
 
 #[cw_serde]
 pub struct NameRecord {
     pub owner: Addr,
+    /// Subdomains never expire themselves, they follow their top-level name.
+    pub expires: Expiration,
+    /// The address the name resolves to, the owner if not set
+    pub address: Option<Addr>,
+    /// Text records like avatar, url, email or content hash
+    pub records: BTreeMap<String, String>,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
-pub const NAME_RESOLVER: Map<&[u8], NameRecord> = Map::new("name_resolver");
+
+/// Fees paid for registrations, renewals, transfers and won auctions.
+/// Outstanding bids are not fees, they are held until refunded or the auction is settled.
+#[cw_serde]
+#[derive(Default)]
+pub struct Fees {
+    /// All fees collected since instantiation
+    pub collected: NativeBalance,
+    /// Fees the admin has not withdrawn yet
+    pub available: NativeBalance,
+}
+
+pub const FEES: Item<Fees> = Item::new("fees");
+
+pub struct NameIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, NameRecord, &'a [u8]>,
+}
+
+impl<'a> IndexList<NameRecord> for NameIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<NameRecord>> + '_> {
+        let v: Vec<&dyn Index<NameRecord>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+/// Records of all registered names and subdomains, keyed by name bytes and indexed by owner
+pub fn name_resolver<'a>() -> IndexedMap<'a, &'a [u8], NameRecord, NameIndexes<'a>> {
+    let indexes = NameIndexes {
+        owner: MultiIndex::new(
+            |record| record.owner.clone(),
+            "name_resolver",
+            "name_resolver__owner",
+        ),
+    };
+    IndexedMap::new("name_resolver", indexes)
+}
+
+/// Labels of the subdomains of a name, keyed by (parent, label).
+/// The record of a subdomain is stored in name_resolver under "label.parent".
+pub const SUBDOMAINS: Map<(&str, &str), Empty> = Map::new("subdomains");
+
+/// The name an address chose to be known by, for reverse resolution
+pub const PRIMARY_NAMES: Map<&Addr, String> = Map::new("primary_names");
+
+/// Running and ended but not yet settled auctions, keyed by name
+pub const AUCTIONS: Map<&str, Auction> = Map::new("auctions");
@@ -1,17 +1,32 @@
 #[cfg(test)]
 mod test_module {
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{coin, coins, from_binary, Coin, Deps, DepsMut};
+    use cosmwasm_std::{
+        coin, coins, from_binary, Addr, BankMsg, Coin, Deps, DepsMut, Env, SubMsg, Uint128,
+    };
+    use cw_utils::{Duration, Expiration};
 
     use crate::contract::{execute, instantiate, query};
     use crate::error::ContractError;
-    use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, ResolveRecordResponse};
-    use crate::state::Config;
+    use crate::msg::{
+        AuctionResponse, ExecuteMsg, FeesCollectedResponse, InstantiateMsg, NamesResponse,
+        QueryMsg, RecordsResponse, ResolveRecordResponse, ReverseRecordResponse,
+        SubdomainsResponse,
+    };
+    use crate::state::{AuctionConfig, Config, LengthPrice};
+
+    const REGISTRATION_PERIOD: u64 = 1000;
+    const GRACE_PERIOD: u64 = 100;
+    const AUCTION_DURATION: u64 = 50;
 
     fn assert_name_owner(deps: Deps, name: &str, owner: &str) {
+        assert_name_owner_at(deps, mock_env(), name, Some(owner));
+    }
+
+    fn assert_name_owner_at(deps: Deps, env: Env, name: &str, owner: Option<&str>) {
         let res = query(
             deps,
-            mock_env(),
+            env,
             QueryMsg::ResolveRecord {
                 name: name.to_string(),
             },
@@ -19,7 +34,13 @@ mod test_module {
         .unwrap();
 
         let value: ResolveRecordResponse = from_binary(&res).unwrap();
-        assert_eq!(Some(owner.to_string()), value.address);
+        assert_eq!(owner.map(str::to_string), value.address);
+    }
+
+    fn env_at_height(height: u64) -> Env {
+        let mut env = mock_env();
+        env.block.height = height;
+        env
     }
 
     fn assert_config_state(deps: Deps, expected: Config) {
@@ -30,8 +51,13 @@ mod test_module {
 
     fn mock_init_with_price(deps: DepsMut, purchase_price: Coin, transfer_price: Coin) {
         let msg = InstantiateMsg {
+            admin: None,
             purchase_price: Some(purchase_price),
+            length_prices: vec![],
             transfer_price: Some(transfer_price),
+            registration_period: Duration::Height(REGISTRATION_PERIOD),
+            grace_period: Duration::Height(GRACE_PERIOD),
+            auction: None,
         };
 
         let info = mock_info("creator", &coins(2, "token"));
@@ -41,8 +67,13 @@ mod test_module {
 
     fn mock_init_no_price(deps: DepsMut) {
         let msg = InstantiateMsg {
+            admin: None,
             purchase_price: None,
+            length_prices: vec![],
             transfer_price: None,
+            registration_period: Duration::Height(REGISTRATION_PERIOD),
+            grace_period: Duration::Height(GRACE_PERIOD),
+            auction: None,
         };
 
         let info = mock_info("creator", &coins(2, "token"));
@@ -50,6 +81,29 @@ mod test_module {
             .expect("contract successfully handles InstantiateMsg");
     }
 
+    fn mock_init_with_auction(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            admin: None,
+            purchase_price: Some(coin(2, "token")),
+            length_prices: vec![LengthPrice {
+                max_length: 4,
+                price: coin(10, "token"),
+            }],
+            transfer_price: None,
+            registration_period: Duration::Height(REGISTRATION_PERIOD),
+            grace_period: Duration::Height(GRACE_PERIOD),
+            auction: Some(AuctionConfig {
+                max_length: 3,
+                duration: Duration::Height(AUCTION_DURATION),
+                min_bid: coin(100, "token"),
+            }),
+        };
+
+        let info = mock_info("creator", &[]);
+        let _res = instantiate(deps, mock_env(), info, msg)
+            .expect("contract successfully handles InstantiateMsg");
+    }
+
     fn mock_alice_registers_name(deps: DepsMut, sent: &[Coin]) {
         // alice can register an available name
         let info = mock_info("alice_key", sent);
@@ -69,8 +123,13 @@ mod test_module {
         assert_config_state(
             deps.as_ref(),
             Config {
+                admin: Addr::unchecked("creator"),
                 purchase_price: None,
+                length_prices: vec![],
                 transfer_price: None,
+                registration_period: Duration::Height(REGISTRATION_PERIOD),
+                grace_period: Duration::Height(GRACE_PERIOD),
+                auction: None,
             },
         );
     }
@@ -84,8 +143,13 @@ mod test_module {
         assert_config_state(
             deps.as_ref(),
             Config {
+                admin: Addr::unchecked("creator"),
                 purchase_price: Some(coin(3, "token")),
+                length_prices: vec![],
                 transfer_price: Some(coin(4, "token")),
+                registration_period: Duration::Height(REGISTRATION_PERIOD),
+                grace_period: Duration::Height(GRACE_PERIOD),
+                auction: None,
             },
         );
     }
@@ -106,14 +170,22 @@ mod test_module {
         mock_init_with_price(deps.as_mut(), coin(2, "token"), coin(2, "token"));
         mock_alice_registers_name(deps.as_mut(), &coins(2, "token"));
 
-        // anyone can register an available name with more fees than needed
+        // anyone can register an available name with more fees than needed,
+        // the change is refunded
         let info = mock_info("bob_key", &coins(5, "token"));
         let msg = ExecuteMsg::Register {
             name: "bob".to_string(),
         };
 
-        let _res = execute(deps.as_mut(), mock_env(), info, msg)
+        let res = execute(deps.as_mut(), mock_env(), info, msg)
             .expect("contract successfully handles Register message");
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "bob_key".to_string(),
+                amount: coins(3, "token"),
+            })]
+        );
 
         // querying for name resolves to correct address
         assert_name_owner(deps.as_ref(), "alice", "alice_key");
@@ -178,26 +250,141 @@ mod test_module {
             Err(_) => panic!("Unknown error"),
         }
 
-        // no upper case...
+        // no spaces...
         let msg = ExecuteMsg::Register {
-            name: "LOUD".to_string(),
+            name: "two words".to_string(),
         };
         match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
             Ok(_) => panic!("Must return error"),
-            Err(ContractError::InvalidCharacter { c }) => assert_eq!(c, 'L'),
+            Err(ContractError::InvalidCharacter { c, position, .. }) => {
+                assert_eq!(c, ' ');
+                assert_eq!(position, 3);
+            }
             Err(_) => panic!("Unknown error"),
         }
-        // ... or spaces
+        // ... or symbols
         let msg = ExecuteMsg::Register {
-            name: "two words".to_string(),
+            name: "snow☃man".to_string(),
         };
         match execute(deps.as_mut(), mock_env(), info, msg) {
             Ok(_) => panic!("Must return error"),
-            Err(ContractError::InvalidCharacter { .. }) => {}
+            Err(ContractError::InvalidCharacter {
+                code_point,
+                position,
+                ..
+            }) => {
+                assert_eq!(code_point, "U+2603");
+                assert_eq!(position, 4);
+            }
             Err(_) => panic!("Unknown error"),
         }
     }
 
+    #[test]
+    fn names_are_normalized() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+
+        // names are case-folded and composed before they are stored...
+        let info = mock_info("alice_key", &[]);
+        for name in ["LOUD", "cafe\u{301}", "日本語"] {
+            let msg = ExecuteMsg::Register {
+                name: name.to_string(),
+            };
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+
+        // ... and looked up
+        assert_name_owner(deps.as_ref(), "loud", "alice_key");
+        assert_name_owner(deps.as_ref(), "Loud", "alice_key");
+        assert_name_owner(deps.as_ref(), "caf\u{e9}", "alice_key");
+        assert_name_owner(deps.as_ref(), "CAFE\u{301}", "alice_key");
+        // three characters are long enough, even if they take nine bytes
+        assert_name_owner(deps.as_ref(), "日本語", "alice_key");
+
+        let msg = ExecuteMsg::Register {
+            name: "Caf\u{e9}".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), mock_info("bob_key", &[]), msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameTaken { name }) => assert_eq!(name, "caf\u{e9}"),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        let msg = QueryMsg::NamesByOwner {
+            owner: "alice_key".to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NamesResponse = from_binary(&res).unwrap();
+        assert_eq!(value.names, vec!["caf\u{e9}", "loud", "日本語"]);
+    }
+
+    #[test]
+    fn homographs_are_rejected() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+
+        let msg = ExecuteMsg::Register {
+            name: "paypal".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice_key", &[]), msg).unwrap();
+
+        let info = mock_info("mallory_key", &[]);
+        for name in [
+            // a cyrillic "а" in a latin name
+            "p\u{430}ypal",
+            // greek omicrons in a latin name
+            "g\u{3bf}\u{3bf}gle",
+            // all cyrillic, but looks like "ape"
+            "\u{430}\u{440}\u{435}",
+        ] {
+            let msg = ExecuteMsg::Register {
+                name: name.to_string(),
+            };
+            match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
+                Ok(_) => panic!("Must return error"),
+                Err(ContractError::ConfusableName { .. }) => {}
+                Err(e) => panic!("Unexpected error: {:?}", e),
+            }
+        }
+
+        // fullwidth letters are not allowed at all
+        let msg = ExecuteMsg::Register {
+            name: "\u{ff30}aypal".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InvalidCharacter {
+                code_point,
+                position,
+                ..
+            }) => {
+                assert_eq!(code_point, "U+FF50");
+                assert_eq!(position, 0);
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // case variants are the same name
+        let msg = ExecuteMsg::Register {
+            name: "PayPal".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameTaken { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // names in a single other script are fine
+        let msg = ExecuteMsg::Register {
+            name: "Москва".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_name_owner(deps.as_ref(), "москва", "mallory_key");
+    }
+
     #[test]
     fn fails_on_register_insufficient_fees() {
         let mut deps = mock_dependencies();
@@ -369,4 +556,757 @@ mod test_module {
         let value: ResolveRecordResponse = from_binary(&res).unwrap();
         assert_eq!(None, value.address);
     }
+
+    #[test]
+    fn names_expire() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        let expires = mock_env().block.height + REGISTRATION_PERIOD;
+        assert_name_owner_at(
+            deps.as_ref(),
+            env_at_height(expires - 1),
+            "alice",
+            Some("alice_key"),
+        );
+        assert_name_owner_at(deps.as_ref(), env_at_height(expires), "alice", None);
+
+        // expired names cannot be transferred
+        let info = mock_info("alice_key", &[]);
+        let msg = ExecuteMsg::Transfer {
+            name: "alice".to_string(),
+            to: "bob_key".to_string(),
+        };
+        match execute(deps.as_mut(), env_at_height(expires), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameExpired { name }) => assert_eq!(name, "alice"),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn renew_works_with_fees() {
+        let mut deps = mock_dependencies();
+        mock_init_with_price(deps.as_mut(), coin(2, "token"), coin(2, "token"));
+        mock_alice_registers_name(deps.as_mut(), &coins(2, "token"));
+
+        // renewing costs the purchase price
+        let msg = ExecuteMsg::Renew {
+            name: "alice".to_string(),
+        };
+        let info = mock_info("alice_key", &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // and extends the registration by a period
+        let info = mock_info("alice_key", &coins(2, "token"));
+        execute(deps.as_mut(), mock_env(), info, msg)
+            .expect("contract successfully handles Renew message");
+        let expires = mock_env().block.height + 2 * REGISTRATION_PERIOD;
+        assert_name_owner_at(
+            deps.as_ref(),
+            env_at_height(expires - 1),
+            "alice",
+            Some("alice_key"),
+        );
+        assert_name_owner_at(deps.as_ref(), env_at_height(expires), "alice", None);
+    }
+
+    #[test]
+    fn only_owner_renews_in_grace_period() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        let expires = mock_env().block.height + REGISTRATION_PERIOD;
+        let msg = ExecuteMsg::Renew {
+            name: "alice".to_string(),
+        };
+
+        // bob can neither renew nor register the name during the grace period
+        let info = mock_info("bob_key", &[]);
+        match execute(
+            deps.as_mut(),
+            env_at_height(expires),
+            info.clone(),
+            msg.clone(),
+        ) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let register = ExecuteMsg::Register {
+            name: "alice".to_string(),
+        };
+        match execute(deps.as_mut(), env_at_height(expires), info, register) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameTaken { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // alice can, keeping the original schedule
+        let info = mock_info("alice_key", &[]);
+        execute(deps.as_mut(), env_at_height(expires), info, msg)
+            .expect("contract successfully handles Renew message");
+        let expires = expires + REGISTRATION_PERIOD;
+        assert_name_owner_at(
+            deps.as_ref(),
+            env_at_height(expires - 1),
+            "alice",
+            Some("alice_key"),
+        );
+    }
+
+    #[test]
+    fn register_name_after_grace_period() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        let released = mock_env().block.height + REGISTRATION_PERIOD + GRACE_PERIOD;
+
+        // alice is too late to renew
+        let info = mock_info("alice_key", &[]);
+        let msg = ExecuteMsg::Renew {
+            name: "alice".to_string(),
+        };
+        match execute(deps.as_mut(), env_at_height(released), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameExpired { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // and bob can register it
+        let info = mock_info("bob_key", &[]);
+        let msg = ExecuteMsg::Register {
+            name: "alice".to_string(),
+        };
+        execute(deps.as_mut(), env_at_height(released), info, msg)
+            .expect("contract successfully handles Register message");
+        assert_name_owner_at(
+            deps.as_ref(),
+            env_at_height(released),
+            "alice",
+            Some("bob_key"),
+        );
+    }
+
+    #[test]
+    fn set_address_and_records() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        // only the owner can set records
+        let info = mock_info("bob_key", &[]);
+        let msg = ExecuteMsg::SetAddress {
+            name: "alice".to_string(),
+            address: "bob_key".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // alice points her name at a contract, but keeps owning it
+        let info = mock_info("alice_key", &[]);
+        let msg = ExecuteMsg::SetAddress {
+            name: "alice".to_string(),
+            address: "contract_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg)
+            .expect("contract successfully handles SetAddress message");
+        assert_name_owner(deps.as_ref(), "alice", "contract_key");
+
+        let msg = ExecuteMsg::SetRecord {
+            name: "alice".to_string(),
+            key: "url".to_string(),
+            value: "https://alice.example".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg)
+            .expect("contract successfully handles SetRecord message");
+        let msg = ExecuteMsg::SetRecord {
+            name: "alice".to_string(),
+            key: "avatar".to_string(),
+            value: "ipfs://avatar".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // an empty value removes a record
+        let msg = ExecuteMsg::SetRecord {
+            name: "alice".to_string(),
+            key: "avatar".to_string(),
+            value: "".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let query_msg = QueryMsg::Records {
+            name: "alice".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), query_msg.clone()).unwrap();
+        let value: RecordsResponse = from_binary(&res).unwrap();
+        assert_eq!(Some("alice_key".to_string()), value.owner);
+        assert_eq!(Some("contract_key".to_string()), value.address);
+        assert_eq!(1, value.records.len());
+        assert_eq!("https://alice.example", value.records["url"]);
+
+        // records of expired names are not returned
+        let expires = mock_env().block.height + REGISTRATION_PERIOD;
+        let res = query(deps.as_ref(), env_at_height(expires), query_msg).unwrap();
+        let value: RecordsResponse = from_binary(&res).unwrap();
+        assert_eq!(None, value.address);
+        assert!(value.records.is_empty());
+    }
+
+    fn query_subdomains(deps: Deps, parent: &str) -> Vec<String> {
+        let msg = QueryMsg::Subdomains {
+            parent: parent.to_string(),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps, mock_env(), msg).unwrap();
+        let value: SubdomainsResponse = from_binary(&res).unwrap();
+        value.labels
+    }
+
+    #[test]
+    fn subdomains_work() {
+        let mut deps = mock_dependencies();
+        mock_init_with_price(deps.as_mut(), coin(2, "token"), coin(2, "token"));
+        mock_alice_registers_name(deps.as_mut(), &coins(2, "token"));
+
+        // dots are reserved for subdomains
+        let info = mock_info("bob_key", &coins(2, "token"));
+        let msg = ExecuteMsg::Register {
+            name: "team.alice".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InvalidCharacter { c, .. }) => assert_eq!(c, '.'),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // only the owner of the parent can register subdomains
+        let msg = ExecuteMsg::RegisterSubdomain {
+            parent: "alice".to_string(),
+            label: "team".to_string(),
+            owner: "bob_key".to_string(),
+        };
+        let info = mock_info("bob_key", &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // and does not pay for them
+        let info = mock_info("alice_key", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg)
+            .expect("contract successfully handles RegisterSubdomain message");
+        assert_name_owner(deps.as_ref(), "team.alice", "bob_key");
+
+        // the subdomain owner can add further levels
+        let msg = ExecuteMsg::RegisterSubdomain {
+            parent: "team.alice".to_string(),
+            label: "a".to_string(),
+            owner: "carl_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob_key", &[]), msg).unwrap();
+        assert_name_owner(deps.as_ref(), "a.team.alice", "carl_key");
+
+        let msg = ExecuteMsg::RegisterSubdomain {
+            parent: "alice".to_string(),
+            label: "dev".to_string(),
+            owner: "alice_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            query_subdomains(deps.as_ref(), "alice"),
+            vec!["dev", "team"]
+        );
+
+        // the parent owner can take a subdomain back
+        let msg = ExecuteMsg::TransferSubdomain {
+            parent: "alice".to_string(),
+            label: "team".to_string(),
+            to: "alice_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_name_owner(deps.as_ref(), "team.alice", "alice_key");
+
+        // subdomains expire with their top-level name
+        let expires = mock_env().block.height + REGISTRATION_PERIOD;
+        assert_name_owner_at(deps.as_ref(), env_at_height(expires), "a.team.alice", None);
+
+        // revoking removes all levels below
+        let msg = ExecuteMsg::RevokeSubdomain {
+            parent: "alice".to_string(),
+            label: "team".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_name_owner_at(deps.as_ref(), mock_env(), "team.alice", None);
+        assert_name_owner_at(deps.as_ref(), mock_env(), "a.team.alice", None);
+        assert_eq!(query_subdomains(deps.as_ref(), "alice"), vec!["dev"]);
+        assert!(query_subdomains(deps.as_ref(), "team.alice").is_empty());
+    }
+
+    #[test]
+    fn reregistered_name_drops_subdomains() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        let msg = ExecuteMsg::RegisterSubdomain {
+            parent: "alice".to_string(),
+            label: "team".to_string(),
+            owner: "alice_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice_key", &[]), msg).unwrap();
+
+        // bob registers the released name and does not inherit alice's subdomains
+        let released = mock_env().block.height + REGISTRATION_PERIOD + GRACE_PERIOD;
+        let msg = ExecuteMsg::Register {
+            name: "alice".to_string(),
+        };
+        execute(
+            deps.as_mut(),
+            env_at_height(released),
+            mock_info("bob_key", &[]),
+            msg,
+        )
+        .unwrap();
+        assert_name_owner_at(deps.as_ref(), env_at_height(released), "team.alice", None);
+        assert!(query_subdomains(deps.as_ref(), "alice").is_empty());
+    }
+
+    fn assert_primary_name(deps: Deps, address: &str, name: Option<&str>) {
+        let msg = QueryMsg::ReverseRecord {
+            address: address.to_string(),
+        };
+        let res = query(deps, mock_env(), msg).unwrap();
+        let value: ReverseRecordResponse = from_binary(&res).unwrap();
+        assert_eq!(name.map(str::to_string), value.name);
+    }
+
+    #[test]
+    fn reverse_resolution_works() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+        assert_primary_name(deps.as_ref(), "alice_key", None);
+
+        // bob cannot claim alice's name
+        let msg = ExecuteMsg::SetPrimaryName {
+            name: "alice".to_string(),
+        };
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bob_key", &[]),
+            msg.clone(),
+        ) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        let info = mock_info("alice_key", &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg)
+            .expect("contract successfully handles SetPrimaryName message");
+        assert_primary_name(deps.as_ref(), "alice_key", Some("alice"));
+
+        // transferring the name clears the primary name
+        let msg = ExecuteMsg::Transfer {
+            name: "alice".to_string(),
+            to: "bob_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_primary_name(deps.as_ref(), "alice_key", None);
+        assert_primary_name(deps.as_ref(), "bob_key", None);
+    }
+
+    #[test]
+    fn names_by_owner_works() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+        mock_alice_registers_name(deps.as_mut(), &[]);
+
+        let info = mock_info("alice_key", &[]);
+        for name in ["zeta", "beta"] {
+            let msg = ExecuteMsg::Register {
+                name: name.to_string(),
+            };
+            execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        }
+        let msg = ExecuteMsg::RegisterSubdomain {
+            parent: "zeta".to_string(),
+            label: "team".to_string(),
+            owner: "alice_key".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::Register {
+            name: "bob".to_string(),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("bob_key", &[]), msg).unwrap();
+
+        let msg = QueryMsg::NamesByOwner {
+            owner: "alice_key".to_string(),
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NamesResponse = from_binary(&res).unwrap();
+        assert_eq!(value.names, vec!["alice", "beta"]);
+
+        let msg = QueryMsg::NamesByOwner {
+            owner: "alice_key".to_string(),
+            start_after: Some("beta".to_string()),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: NamesResponse = from_binary(&res).unwrap();
+        assert_eq!(value.names, vec!["team.zeta", "zeta"]);
+    }
+
+    #[test]
+    fn length_prices_work() {
+        let mut deps = mock_dependencies();
+        mock_init_with_auction(deps.as_mut());
+
+        // short names cost the price of their tier, to register and to renew
+        let info = mock_info("alice_key", &coins(2, "token"));
+        let msg = ExecuteMsg::Register {
+            name: "abcd".to_string(),
+        };
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let info = mock_info("alice_key", &coins(10, "token"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_name_owner(deps.as_ref(), "abcd", "alice_key");
+
+        let msg = ExecuteMsg::Renew {
+            name: "abcd".to_string(),
+        };
+        let info = mock_info("alice_key", &coins(2, "token"));
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // longer names cost the purchase price
+        mock_alice_registers_name(deps.as_mut(), &coins(2, "token"));
+        assert_name_owner(deps.as_ref(), "alice", "alice_key");
+    }
+
+    fn query_auction(deps: Deps, name: &str) -> AuctionResponse {
+        let msg = QueryMsg::Auction {
+            name: name.to_string(),
+        };
+        let res = query(deps, mock_env(), msg).unwrap();
+        from_binary(&res).unwrap()
+    }
+
+    #[test]
+    fn auction_works() {
+        let mut deps = mock_dependencies();
+        mock_init_with_auction(deps.as_mut());
+
+        // premium names cannot be registered directly
+        let msg = ExecuteMsg::Register {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("bob_key", &coins(1000, "token"));
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::AuctionRequired { name }) => assert_eq!(name, "bob"),
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        // and other names are not auctioned
+        let msg = ExecuteMsg::Bid {
+            name: "alice".to_string(),
+        };
+        let info = mock_info("alice_key", &coins(1000, "token"));
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NotAuctioned { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // the first bid must reach the minimum and starts the auction
+        let bid = ExecuteMsg::Bid {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("alice_key", &coins(99, "token"));
+        match execute(deps.as_mut(), mock_env(), info, bid.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::BidTooLow { min_bid, .. }) => {
+                assert_eq!(min_bid, Uint128::new(100))
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let info = mock_info("alice_key", &coins(100, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, bid.clone()).unwrap();
+        assert_eq!(res.messages.len(), 0);
+
+        let ends = mock_env().block.height + AUCTION_DURATION;
+        assert_eq!(
+            query_auction(deps.as_ref(), "bob"),
+            AuctionResponse {
+                bidder: Some("alice_key".to_string()),
+                bid: Some(coin(100, "token")),
+                ends: Some(Expiration::AtHeight(ends)),
+            }
+        );
+
+        // later bids must top the highest bid, which is refunded
+        let info = mock_info("bob_key", &coins(100, "token"));
+        match execute(deps.as_mut(), mock_env(), info, bid.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::BidTooLow { min_bid, .. }) => {
+                assert_eq!(min_bid, Uint128::new(101))
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let info = mock_info("bob_key", &coins(150, "token"));
+        let res = execute(deps.as_mut(), mock_env(), info, bid.clone()).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "alice_key".to_string(),
+                amount: coins(100, "token"),
+            })]
+        );
+
+        // the auction can only be settled once it ended
+        let settle = ExecuteMsg::SettleAuction {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("anyone", &[]);
+        match execute(
+            deps.as_mut(),
+            env_at_height(ends - 1),
+            info.clone(),
+            settle.clone(),
+        ) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::AuctionNotEnded { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let late = mock_info("alice_key", &coins(200, "token"));
+        match execute(deps.as_mut(), env_at_height(ends), late, bid.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::AuctionEnded { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        // anyone can settle it, the highest bidder gets the name
+        execute(deps.as_mut(), env_at_height(ends), info, settle).unwrap();
+        assert_name_owner_at(deps.as_ref(), env_at_height(ends), "bob", Some("bob_key"));
+        assert_eq!(
+            query_auction(deps.as_ref(), "bob"),
+            AuctionResponse {
+                bidder: None,
+                bid: None,
+                ends: None,
+            }
+        );
+
+        // a registered name cannot be auctioned again
+        let info = mock_info("alice_key", &coins(200, "token"));
+        match execute(deps.as_mut(), env_at_height(ends), info, bid) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::NameTaken { .. }) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn update_config_works() {
+        let mut deps = mock_dependencies();
+        mock_init_no_price(deps.as_mut());
+
+        let msg = ExecuteMsg::UpdateConfig {
+            admin: None,
+            purchase_price: Some(coin(5, "token")),
+            length_prices: vec![LengthPrice {
+                max_length: 3,
+                price: coin(50, "token"),
+            }],
+            transfer_price: Some(coin(1, "token")),
+            auction: None,
+        };
+
+        // only the admin can update the config
+        let info = mock_info("alice_key", &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        let info = mock_info("creator", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_config_state(
+            deps.as_ref(),
+            Config {
+                admin: Addr::unchecked("creator"),
+                purchase_price: Some(coin(5, "token")),
+                length_prices: vec![LengthPrice {
+                    max_length: 3,
+                    price: coin(50, "token"),
+                }],
+                transfer_price: Some(coin(1, "token")),
+                registration_period: Duration::Height(REGISTRATION_PERIOD),
+                grace_period: Duration::Height(GRACE_PERIOD),
+                auction: None,
+            },
+        );
+
+        // and the new prices apply
+        let msg = ExecuteMsg::Register {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("bob_key", &coins(5, "token"));
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InsufficientFundsSend {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    fn query_fees_collected(deps: Deps) -> FeesCollectedResponse {
+        let res = query(deps, mock_env(), QueryMsg::FeesCollected {}).unwrap();
+        from_binary(&res).unwrap()
+    }
+
+    #[test]
+    fn fees_are_collected_and_withdrawn() {
+        let mut deps = mock_dependencies();
+        mock_init_with_auction(deps.as_mut());
+
+        // registration fees and won auctions are collected, bids are not
+        mock_alice_registers_name(deps.as_mut(), &[coin(5, "token"), coin(1, "earth")]);
+        let bid = ExecuteMsg::Bid {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("bob_key", &coins(100, "token"));
+        execute(deps.as_mut(), mock_env(), info, bid).unwrap();
+        assert_eq!(
+            query_fees_collected(deps.as_ref()),
+            FeesCollectedResponse {
+                collected: coins(2, "token"),
+                available: coins(2, "token"),
+            }
+        );
+
+        let ends = mock_env().block.height + AUCTION_DURATION;
+        let settle = ExecuteMsg::SettleAuction {
+            name: "bob".to_string(),
+        };
+        let info = mock_info("anyone", &[]);
+        execute(deps.as_mut(), env_at_height(ends), info, settle).unwrap();
+        assert_eq!(
+            query_fees_collected(deps.as_ref()),
+            FeesCollectedResponse {
+                collected: coins(102, "token"),
+                available: coins(102, "token"),
+            }
+        );
+
+        // only the admin can withdraw fees
+        let msg = ExecuteMsg::WithdrawFees {
+            to: "treasury".to_string(),
+            amount: coins(100, "token"),
+        };
+        let info = mock_info("alice_key", &[]);
+        match execute(deps.as_mut(), mock_env(), info, msg.clone()) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+
+        let info = mock_info("creator", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: "treasury".to_string(),
+                amount: coins(100, "token"),
+            })]
+        );
+        assert_eq!(
+            query_fees_collected(deps.as_ref()),
+            FeesCollectedResponse {
+                collected: coins(102, "token"),
+                available: coins(2, "token"),
+            }
+        );
+
+        // and no more than is available
+        let msg = ExecuteMsg::WithdrawFees {
+            to: "treasury".to_string(),
+            amount: coins(3, "token"),
+        };
+        match execute(deps.as_mut(), mock_env(), info.clone(), msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::InsufficientFees {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        let msg = ExecuteMsg::WithdrawFees {
+            to: "treasury".to_string(),
+            amount: coins(0, "token"),
+        };
+        match execute(deps.as_mut(), mock_env(), info, msg) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::EmptyWithdrawal {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn admin_can_be_handed_over() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            admin: Some("admin_key".to_string()),
+            purchase_price: None,
+            length_prices: vec![],
+            transfer_price: None,
+            registration_period: Duration::Height(REGISTRATION_PERIOD),
+            grace_period: Duration::Height(GRACE_PERIOD),
+            auction: None,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig {
+            admin: Some("new_admin_key".to_string()),
+            purchase_price: None,
+            length_prices: vec![],
+            transfer_price: None,
+            auction: None,
+        };
+        match execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            msg.clone(),
+        ) {
+            Ok(_) => panic!("Must return error"),
+            Err(ContractError::Unauthorized {}) => {}
+            Err(e) => panic!("Unexpected error: {:?}", e),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("admin_key", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::Config {}).unwrap();
+        let value: Config = from_binary(&res).unwrap();
+        assert_eq!(value.admin, Addr::unchecked("new_admin_key"));
+    }
 }
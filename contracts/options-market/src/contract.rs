@@ -0,0 +1,528 @@
+use cosmwasm_std::{
+    entry_point, to_binary, BankMsg, Binary, Coin, Deps, DepsMut, Env, MessageInfo, Order,
+    Response, StdResult,
+};
+use cw_storage_plus::Bound;
+
+use crate::error::ContractError;
+use crate::msg::{ExecuteMsg, InstantiateMsg, OptionResponse, OptionsResponse, QueryMsg};
+use crate::state::{options, OptionInfo, OPTION_COUNT};
+
+#[entry_point]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    OPTION_COUNT.save(deps.storage, &0)?;
+    Ok(Response::default())
+}
+
+#[entry_point]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Write {
+            counter_offer,
+            expires,
+        } => execute_write(deps, env, info, counter_offer, expires),
+        ExecuteMsg::Transfer { id, recipient } => execute_transfer(deps, env, info, id, recipient),
+        ExecuteMsg::Execute { id } => execute_execute(deps, env, info, id),
+        ExecuteMsg::Burn { id } => execute_burn(deps, env, info, id),
+    }
+}
+
+pub fn execute_write(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    counter_offer: Vec<Coin>,
+    expires: u64,
+) -> Result<Response, ContractError> {
+    if expires <= env.block.height {
+        return Err(ContractError::OptionExpired { expired: expires });
+    }
+    if info.funds.is_empty() {
+        return Err(ContractError::EmptyCollateral {});
+    }
+
+    let id = OPTION_COUNT.load(deps.storage)? + 1;
+    OPTION_COUNT.save(deps.storage, &id)?;
+
+    let option = OptionInfo {
+        creator: info.sender.clone(),
+        owner: info.sender,
+        collateral: info.funds,
+        counter_offer,
+        expires,
+    };
+    options().save(deps.storage, id, &option)?;
+
+    let res =
+        Response::new().add_attributes([("action", "write"), ("id", id.to_string().as_str())]);
+    Ok(res)
+}
+
+pub fn execute_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    id: u64,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    // ensure msg sender is the owner
+    let mut option = options().load(deps.storage, id)?;
+    if info.sender != option.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // set new owner, this also moves the option in the owner index
+    option.owner = deps.api.addr_validate(&recipient)?;
+    options().save(deps.storage, id, &option)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "transfer"),
+        ("id", id.to_string().as_str()),
+        ("owner", recipient.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    // ensure msg sender is the owner
+    let option = options().load(deps.storage, id)?;
+    if info.sender != option.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // ensure not expired
+    if env.block.height >= option.expires {
+        return Err(ContractError::OptionExpired {
+            expired: option.expires,
+        });
+    }
+
+    // ensure sending proper counter_offer
+    if info.funds != option.counter_offer {
+        return Err(ContractError::CounterOfferMismatch {
+            offer: info.funds,
+            counter_offer: option.counter_offer,
+        });
+    }
+
+    // release counter_offer to creator
+    let mut res = Response::new();
+    res = res.add_message(BankMsg::Send {
+        to_address: option.creator.to_string(),
+        amount: option.counter_offer,
+    });
+
+    // release collateral to sender
+    res = res.add_message(BankMsg::Send {
+        to_address: option.owner.to_string(),
+        amount: option.collateral,
+    });
+
+    // delete the option
+    options().remove(deps.storage, id)?;
+
+    res = res.add_attributes([("action", "execute"), ("id", id.to_string().as_str())]);
+    Ok(res)
+}
+
+pub fn execute_burn(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    // ensure is expired
+    let option = options().load(deps.storage, id)?;
+    if env.block.height < option.expires {
+        return Err(ContractError::OptionNotExpired {
+            expires: option.expires,
+        });
+    }
+
+    // ensure sending proper counter_offer
+    if !info.funds.is_empty() {
+        return Err(ContractError::FundsSentWithBurn {});
+    }
+
+    // release collateral to creator
+    let mut res = Response::new();
+    res = res.add_message(BankMsg::Send {
+        to_address: option.creator.to_string(),
+        amount: option.collateral,
+    });
+
+    // delete the option
+    options().remove(deps.storage, id)?;
+
+    res = res.add_attributes([("action", "burn"), ("id", id.to_string().as_str())]);
+    Ok(res)
+}
+
+#[entry_point]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Option { id } => to_binary(&query_option(deps, id)?),
+        QueryMsg::OptionsByOwner {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_options_by_owner(deps, owner, start_after, limit)?),
+        QueryMsg::OptionsByCreator {
+            creator,
+            start_after,
+            limit,
+        } => to_binary(&query_options_by_creator(
+            deps,
+            creator,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::Expiring { start_after, limit } => {
+            to_binary(&query_expiring(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn to_response(id: u64, option: OptionInfo) -> OptionResponse {
+    OptionResponse {
+        id,
+        creator: option.creator,
+        owner: option.owner,
+        collateral: option.collateral,
+        counter_offer: option.counter_offer,
+        expires: option.expires,
+    }
+}
+
+fn query_option(deps: Deps, id: u64) -> StdResult<OptionResponse> {
+    let option = options().load(deps.storage, id)?;
+    Ok(to_response(id, option))
+}
+
+// Limits for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+fn query_options_by_owner(
+    deps: Deps,
+    owner: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OptionsResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let items: StdResult<Vec<_>> = options()
+        .idx
+        .owner
+        .prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, option)| to_response(id, option)))
+        .collect();
+    Ok(OptionsResponse { options: items? })
+}
+
+fn query_options_by_creator(
+    deps: Deps,
+    creator: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<OptionsResponse> {
+    let creator = deps.api.addr_validate(&creator)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let items: StdResult<Vec<_>> = options()
+        .idx
+        .creator
+        .prefix(creator)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, option)| to_response(id, option)))
+        .collect();
+    Ok(OptionsResponse { options: items? })
+}
+
+fn query_expiring(
+    deps: Deps,
+    start_after: Option<(u64, u64)>,
+    limit: Option<u32>,
+) -> StdResult<OptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    // the index is keyed by (expires, id), the cursor does not need to exist anymore
+    let start = start_after.map(Bound::exclusive);
+    let items: StdResult<Vec<_>> = options()
+        .idx
+        .expires
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(id, option)| to_response(id, option)))
+        .collect();
+    Ok(OptionsResponse { options: items? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{attr, coins, Addr, CosmosMsg};
+
+    fn write(deps: DepsMut, creator: &str, expires: u64) -> u64 {
+        let info = mock_info(creator, &coins(1, "BTC"));
+        let res = execute_write(deps, mock_env(), info, coins(40, "ETH"), expires).unwrap();
+        res.attributes[1].value.parse().unwrap()
+    }
+
+    fn ids(res: OptionsResponse) -> Vec<u64> {
+        res.options.into_iter().map(|option| option.id).collect()
+    }
+
+    #[test]
+    fn write_option() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            InstantiateMsg {},
+        )
+        .unwrap();
+
+        // collateral is required
+        let info = mock_info("creator", &[]);
+        let err =
+            execute_write(deps.as_mut(), mock_env(), info, coins(40, "ETH"), 100_000).unwrap_err();
+        match err {
+            ContractError::EmptyCollateral {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // cannot write expired options
+        let info = mock_info("creator", &coins(1, "BTC"));
+        let err = execute_write(deps.as_mut(), mock_env(), info, coins(40, "ETH"), 1).unwrap_err();
+        match err {
+            ContractError::OptionExpired { expired } => assert_eq!(expired, 1),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let info = mock_info("creator", &coins(1, "BTC"));
+        let res =
+            execute_write(deps.as_mut(), mock_env(), info, coins(40, "ETH"), 100_000).unwrap();
+        assert_eq!(0, res.messages.len());
+        assert_eq!(res.attributes[1], attr("id", "1"));
+        assert_eq!(2, write(deps.as_mut(), "creator", 100_000));
+
+        let res = query_option(deps.as_ref(), 1).unwrap();
+        assert_eq!(
+            res,
+            OptionResponse {
+                id: 1,
+                creator: Addr::unchecked("creator"),
+                owner: Addr::unchecked("creator"),
+                collateral: coins(1, "BTC"),
+                counter_offer: coins(40, "ETH"),
+                expires: 100_000,
+            }
+        );
+    }
+
+    #[test]
+    fn transfer_updates_owner_index() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            InstantiateMsg {},
+        )
+        .unwrap();
+        for _ in 0..3 {
+            write(deps.as_mut(), "creator", 100_000);
+        }
+
+        // random cannot transfer
+        let info = mock_info("anyone", &[]);
+        let err =
+            execute_transfer(deps.as_mut(), mock_env(), info, 2, "anyone".to_string()).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // owner can transfer
+        let info = mock_info("creator", &[]);
+        execute_transfer(deps.as_mut(), mock_env(), info, 2, "someone".to_string()).unwrap();
+
+        let res = query_options_by_owner(deps.as_ref(), "creator".to_string(), None, None);
+        assert_eq!(ids(res.unwrap()), vec![1, 3]);
+        let res = query_options_by_owner(deps.as_ref(), "someone".to_string(), None, None);
+        assert_eq!(ids(res.unwrap()), vec![2]);
+        // the creator does not change
+        let res = query_options_by_creator(deps.as_ref(), "creator".to_string(), None, None);
+        assert_eq!(ids(res.unwrap()), vec![1, 2, 3]);
+        let res = query_options_by_creator(deps.as_ref(), "creator".to_string(), Some(1), Some(1));
+        assert_eq!(ids(res.unwrap()), vec![2]);
+    }
+
+    #[test]
+    fn execute() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            InstantiateMsg {},
+        )
+        .unwrap();
+        let id = write(deps.as_mut(), "creator", 100_000);
+        let other = write(deps.as_mut(), "creator", 100_000);
+        let info = mock_info("creator", &[]);
+        execute_transfer(deps.as_mut(), mock_env(), info, id, "owner".to_string()).unwrap();
+
+        let amount = coins(40, "ETH");
+        // random cannot execute
+        let info = mock_info("creator", &amount);
+        let err = execute_execute(deps.as_mut(), mock_env(), info, id).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // expired cannot execute
+        let info = mock_info("owner", &amount);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let err = execute_execute(deps.as_mut(), env, info, id).unwrap_err();
+        match err {
+            ContractError::OptionExpired { expired } => assert_eq!(expired, 100_000),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // bad counter_offer cannot execute
+        let info = mock_info("owner", &coins(39, "ETH"));
+        let err = execute_execute(deps.as_mut(), mock_env(), info, id).unwrap_err();
+        match err {
+            ContractError::CounterOfferMismatch { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // proper execution
+        let info = mock_info("owner", &amount);
+        let res = execute_execute(deps.as_mut(), mock_env(), info, id).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount,
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(1, "BTC"),
+            })
+        );
+
+        // only the executed option is deleted, also from the indexes
+        let _ = query_option(deps.as_ref(), id).unwrap_err();
+        query_option(deps.as_ref(), other).unwrap();
+        let res = query_options_by_owner(deps.as_ref(), "owner".to_string(), None, None);
+        assert!(res.unwrap().options.is_empty());
+        let res = query_options_by_creator(deps.as_ref(), "creator".to_string(), None, None);
+        assert_eq!(ids(res.unwrap()), vec![other]);
+    }
+
+    #[test]
+    fn burn() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            InstantiateMsg {},
+        )
+        .unwrap();
+        let id = write(deps.as_mut(), "creator", 100_000);
+
+        // non-expired cannot burn
+        let info = mock_info("anyone", &[]);
+        let err = execute_burn(deps.as_mut(), mock_env(), info, id).unwrap_err();
+        match err {
+            ContractError::OptionNotExpired { expires } => assert_eq!(expires, 100_000),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // with funds cannot burn
+        let info = mock_info("anyone", &coins(40, "ETH"));
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let err = execute_burn(deps.as_mut(), env.clone(), info, id).unwrap_err();
+        match err {
+            ContractError::FundsSentWithBurn {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // expired returns funds
+        let info = mock_info("anyone", &[]);
+        let res = execute_burn(deps.as_mut(), env, info, id).unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "BTC"),
+            })
+        );
+        let _ = query_option(deps.as_ref(), id).unwrap_err();
+    }
+
+    #[test]
+    fn expiring() {
+        let mut deps = mock_dependencies();
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("anyone", &[]),
+            InstantiateMsg {},
+        )
+        .unwrap();
+        write(deps.as_mut(), "alice", 300_000);
+        write(deps.as_mut(), "bob", 100_000);
+        write(deps.as_mut(), "alice", 200_000);
+        write(deps.as_mut(), "bob", 100_000);
+
+        // ordered by expiration, options expiring at the same height by id
+        let res = query_expiring(deps.as_ref(), None, None).unwrap();
+        assert_eq!(ids(res), vec![2, 4, 3, 1]);
+        let res = query_expiring(deps.as_ref(), None, Some(1)).unwrap();
+        assert_eq!(ids(res), vec![2]);
+        let res = query_expiring(deps.as_ref(), Some((100_000, 2)), Some(2)).unwrap();
+        assert_eq!(ids(res), vec![4, 3]);
+        let res = query_expiring(deps.as_ref(), Some((200_000, 3)), None).unwrap();
+        assert_eq!(ids(res), vec![1]);
+
+        // the last option of a page can be burned before the next page is loaded
+        let mut env = mock_env();
+        env.block.height = 100_000;
+        execute_burn(deps.as_mut(), env, mock_info("anyone", &[]), 2).unwrap();
+        let res = query_expiring(deps.as_ref(), Some((100_000, 2)), None).unwrap();
+        assert_eq!(ids(res), vec![4, 3, 1]);
+    }
+}
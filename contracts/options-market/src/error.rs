@@ -0,0 +1,29 @@
+use cosmwasm_std::{Coin, StdError};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("expired option (expired {expired:?})")]
+    OptionExpired { expired: u64 },
+
+    #[error("not expired option (expires {expires:?})")]
+    OptionNotExpired { expires: u64 },
+
+    #[error("unauthorized")]
+    Unauthorized {},
+
+    #[error("must send exact counter offer (offer {offer:?}, counter_offer: {counter_offer:?})")]
+    CounterOfferMismatch {
+        offer: Vec<Coin>,
+        counter_offer: Vec<Coin>,
+    },
+
+    #[error("must send collateral to write an option")]
+    EmptyCollateral {},
+
+    #[error("do not send funds with burn")]
+    FundsSentWithBurn {},
+}
@@ -0,0 +1,65 @@
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::{Addr, Coin};
+
+#[cw_serde]
+pub struct InstantiateMsg {}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    /// Writes a new option, with the funds sent along as collateral.
+    /// The sender is the creator and initial owner.
+    Write {
+        counter_offer: Vec<Coin>,
+        expires: u64,
+    },
+    /// Owner can transfer to a new owner
+    Transfer { id: u64, recipient: String },
+    /// Owner can post counter_offer on unexpired option to execute and get the collateral
+    Execute { id: u64 },
+    /// Burn will release collateral to the creator if expired
+    Burn { id: u64 },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    #[returns(OptionResponse)]
+    Option { id: u64 },
+    /// Options held by the given owner, ordered by id
+    #[returns(OptionsResponse)]
+    OptionsByOwner {
+        owner: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Options written by the given creator, ordered by id
+    #[returns(OptionsResponse)]
+    OptionsByCreator {
+        creator: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// All options ordered by expiration, the first to expire first.
+    /// `start_after` is the (expires, id) of the last option of the previous page,
+    /// so paging keeps working when that option was executed or burned meanwhile.
+    #[returns(OptionsResponse)]
+    Expiring {
+        start_after: Option<(u64, u64)>,
+        limit: Option<u32>,
+    },
+}
+
+#[cw_serde]
+pub struct OptionResponse {
+    pub id: u64,
+    pub creator: Addr,
+    pub owner: Addr,
+    pub collateral: Vec<Coin>,
+    pub counter_offer: Vec<Coin>,
+    pub expires: u64,
+}
+
+#[cw_serde]
+pub struct OptionsResponse {
+    pub options: Vec<OptionResponse>,
+}
@@ -0,0 +1,39 @@
+use cosmwasm_schema::cw_serde;
+
+use cosmwasm_std::{Addr, Coin};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+
+#[cw_serde]
+pub struct OptionInfo {
+    pub creator: Addr,
+    pub owner: Addr,
+    pub collateral: Vec<Coin>,
+    pub counter_offer: Vec<Coin>,
+    pub expires: u64,
+}
+
+pub struct OptionIndexes<'a> {
+    pub owner: MultiIndex<'a, Addr, OptionInfo, u64>,
+    pub creator: MultiIndex<'a, Addr, OptionInfo, u64>,
+    pub expires: MultiIndex<'a, u64, OptionInfo, u64>,
+}
+
+impl<'a> IndexList<OptionInfo> for OptionIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<OptionInfo>> + '_> {
+        let v: Vec<&dyn Index<OptionInfo>> = vec![&self.owner, &self.creator, &self.expires];
+        Box::new(v.into_iter())
+    }
+}
+
+/// All written options that were not executed or burned yet, keyed by id
+pub fn options<'a>() -> IndexedMap<'a, u64, OptionInfo, OptionIndexes<'a>> {
+    let indexes = OptionIndexes {
+        owner: MultiIndex::new(|o| o.owner.clone(), "options", "options__owner"),
+        creator: MultiIndex::new(|o| o.creator.clone(), "options", "options__creator"),
+        expires: MultiIndex::new(|o| o.expires, "options", "options__expires"),
+    };
+    IndexedMap::new("options", indexes)
+}
+
+/// Number of options written so far, used to assign ids
+pub const OPTION_COUNT: Item<u64> = Item::new("option_count");
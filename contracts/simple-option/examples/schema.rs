@@ -1,11 +1,12 @@
 use cosmwasm_schema::write_api;
 
-use simple_option::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use simple_option::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         query: QueryMsg,
         execute: ExecuteMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -1,10 +1,31 @@
+use std::cmp::Ordering;
+
 use cosmwasm_std::{
-    entry_point, to_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    entry_point, from_binary, to_binary, Addr, BankMsg, Binary, BlockInfo, Coin, CosmosMsg,
+    Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Storage,
+    Uint128,
+};
+use cw20::{Balance, Cw20Coin, Cw20CoinVerified, Cw20Contract, Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw721::{
+    AllNftInfoResponse, Approval, ApprovalResponse, ContractInfoResponse, Cw721ReceiveMsg,
+    NftInfoResponse, NumTokensResponse, OwnerOfResponse, TokensResponse,
 };
+use cw_utils::{Expiration, NativeBalance};
 
 use crate::error::ContractError;
-use crate::msg::{ConfigResponse, ExecuteMsg, InstantiateMsg, QueryMsg};
-use crate::state::{State, CONFIG};
+use crate::msg::{
+    CashSettlementMsg, ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, OffersResponse,
+    OracleQueryMsg, PriceResponse, QueryMsg, ReceiveMsg, TermsResponse, UnitsResponse,
+};
+use crate::state::{
+    ExerciseStyle, Offer, OptionKind, Settlement, State, APPROVALS, CONFIG, LEGACY_CONFIG, OFFER,
+    OPERATORS,
+};
+
+// The option is the only token of a cw721 collection
+pub const TOKEN_ID: &str = "option";
+const NFT_NAME: &str = "simple-option";
+const NFT_SYMBOL: &str = "OPTION";
 
 #[entry_point]
 pub fn instantiate(
@@ -13,18 +34,51 @@ pub fn instantiate(
     info: MessageInfo,
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
-    if msg.expires <= env.block.height {
+    if msg.expires.is_expired(&env.block) {
         return Err(ContractError::OptionExpired {
             expired: msg.expires,
         });
     }
 
+    let collateral = to_balance(deps.as_ref(), info.funds, msg.cw20_collateral)?;
+    let counter_offer = to_balance(deps.as_ref(), msg.counter_offer, msg.cw20_counter_offer)?;
+    // cw20 collateral can only be sent after instantiation
+    let funded = matches!(collateral, Balance::Native(_));
+
+    let units = msg.units.unwrap_or(1);
+    if units == 0 {
+        return Err(ContractError::ZeroUnits {});
+    }
+    check_divisible(&collateral, units)?;
+    check_divisible(&counter_offer, units)?;
+
+    if let ExerciseStyle::European { exercise_start } = msg.style {
+        // the window must not be empty, which also rejects mixing heights and times
+        if exercise_start.partial_cmp(&msg.expires) != Some(Ordering::Less) {
+            return Err(ContractError::InvalidExerciseWindow {
+                exercise_start,
+                expires: msg.expires,
+            });
+        }
+    }
+
+    let settlement = match msg.cash_settlement {
+        None => Settlement::Physical,
+        Some(cash) => to_cash_settlement(deps.as_ref(), cash, &collateral, &counter_offer)?,
+    };
+
     let state = State {
         creator: info.sender.clone(),
         owner: info.sender.clone(),
-        collateral: info.funds,
-        counter_offer: msg.counter_offer,
+        collateral,
+        counter_offer,
         expires: msg.expires,
+        funded,
+        units,
+        units_remaining: units,
+        kind: msg.kind,
+        style: msg.style,
+        settlement,
     };
 
     CONFIG.save(deps.storage, &state)?;
@@ -32,6 +86,116 @@ pub fn instantiate(
     Ok(Response::default())
 }
 
+// cash settled options pay out of a single collateral denom and ask for nothing in exchange
+fn to_cash_settlement(
+    deps: Deps,
+    cash: CashSettlementMsg,
+    collateral: &Balance,
+    counter_offer: &Balance,
+) -> Result<Settlement, ContractError> {
+    if !counter_offer.is_empty() {
+        return Err(ContractError::CashSettledCounterOffer {});
+    }
+    // the payout is computed in `quote`, so the collateral has to be held in it
+    let in_quote = match collateral {
+        Balance::Native(coins) => coins.0.len() == 1 && coins.0[0].denom == cash.quote,
+        Balance::Cw20(_) => false,
+    };
+    if !in_quote {
+        return Err(ContractError::CashSettledCollateral { quote: cash.quote });
+    }
+    Ok(Settlement::Cash {
+        oracle: deps.api.addr_validate(&cash.oracle)?,
+        base: cash.base,
+        quote: cash.quote,
+        strike: cash.strike,
+        size: cash.size,
+    })
+}
+
+// every unit gets the same share of every denom, so no rounding is ever needed
+fn check_divisible(balance: &Balance, units: u64) -> Result<(), ContractError> {
+    let amounts = match balance {
+        Balance::Native(coins) => coins
+            .0
+            .iter()
+            .map(|coin| (coin.denom.clone(), coin.amount))
+            .collect(),
+        Balance::Cw20(token) => vec![(token.address.to_string(), token.amount)],
+    };
+    for (denom, amount) in amounts {
+        if !(amount % Uint128::from(units)).is_zero() {
+            return Err(ContractError::IndivisibleAmount {
+                denom,
+                amount,
+                units,
+            });
+        }
+    }
+    Ok(())
+}
+
+// the share of `units` out of `total` units, exact as amounts are multiples of the units
+fn share(balance: &Balance, units: u64, total: u64) -> Balance {
+    match balance {
+        Balance::Native(coins) => Balance::from(
+            coins
+                .0
+                .iter()
+                .map(|coin| Coin {
+                    denom: coin.denom.clone(),
+                    amount: coin.amount.multiply_ratio(units, total),
+                })
+                .collect::<Vec<_>>(),
+        ),
+        Balance::Cw20(token) => Balance::Cw20(Cw20CoinVerified {
+            address: token.address.clone(),
+            amount: token.amount.multiply_ratio(units, total),
+        }),
+    }
+}
+
+// an option leg is either some native coins or a single cw20 token
+fn to_balance(
+    deps: Deps,
+    native: Vec<Coin>,
+    cw20: Option<Cw20Coin>,
+) -> Result<Balance, ContractError> {
+    match cw20 {
+        None => Ok(Balance::from(native)),
+        Some(_) if !native.is_empty() => Err(ContractError::MixedAssets {}),
+        Some(token) => Ok(Balance::Cw20(Cw20CoinVerified {
+            address: deps.api.addr_validate(&token.address)?,
+            amount: token.amount,
+        })),
+    }
+}
+
+#[entry_point]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // options of the first release store native coins and a plain height,
+    // their collateral was sent on instantiate
+    if CONFIG.load(deps.storage).is_err() {
+        let legacy = LEGACY_CONFIG.load(deps.storage)?;
+        let state = State {
+            creator: legacy.creator,
+            owner: legacy.owner,
+            collateral: Balance::from(legacy.collateral),
+            counter_offer: Balance::from(legacy.counter_offer),
+            expires: Expiration::AtHeight(legacy.expires),
+            funded: true,
+            units: 1,
+            units_remaining: 1,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            settlement: Settlement::Physical,
+        };
+        CONFIG.save(deps.storage, &state)?;
+    }
+
+    Ok(Response::new().add_attribute("action", "migrate"))
+}
+
 #[entry_point]
 pub fn execute(
     deps: DepsMut,
@@ -41,82 +205,524 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Transfer { recipient } => execute_transfer(deps, env, info, recipient),
-        ExecuteMsg::Execute {} => execute_execute(deps, env, info),
+        ExecuteMsg::Execute { units } => execute_execute(deps, env, info, units),
         ExecuteMsg::Burn {} => execute_burn(deps, env, info),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Offer { price } => execute_offer(deps, env, info, price),
+        ExecuteMsg::CancelOffer {} => execute_cancel_offer(deps, info),
+        ExecuteMsg::Buy {} => execute_buy(deps, env, info),
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => execute_transfer_nft(deps, env, info, recipient, token_id),
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => execute_send_nft(deps, env, info, contract, token_id, msg),
+        ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => execute_approve(deps, env, info, spender, token_id, expires),
+        ExecuteMsg::Revoke { spender, token_id } => {
+            execute_revoke(deps, env, info, spender, token_id)
+        }
+        ExecuteMsg::ApproveAll { operator, expires } => {
+            execute_approve_all(deps, env, info, operator, expires)
+        }
+        ExecuteMsg::RevokeAll { operator } => execute_revoke_all(deps, info, operator),
     }
 }
 
-pub fn execute_transfer(
+pub fn execute_receive(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
-    recipient: String,
+    wrapped: Cw20ReceiveMsg,
 ) -> Result<Response, ContractError> {
-    // ensure msg sender is the owner
+    // the message sender is the cw20 contract, the tokens come from the cw20 sender
+    let sender = deps.api.addr_validate(&wrapped.sender)?;
+    let tokens = Balance::Cw20(Cw20CoinVerified {
+        address: info.sender,
+        amount: wrapped.amount,
+    });
+
+    let msg: ReceiveMsg = from_binary(&wrapped.msg)?;
+    match msg {
+        ReceiveMsg::Fund {} => execute_fund(deps, env, sender, tokens),
+        ReceiveMsg::Execute { units } => exercise(deps, env, sender, tokens, units),
+    }
+}
+
+pub fn execute_fund(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    tokens: Balance,
+) -> Result<Response, ContractError> {
+    // ensure msg sender is the creator
     let mut state = CONFIG.load(deps.storage)?;
-    if info.sender != state.owner {
+    if sender != state.creator {
         return Err(ContractError::Unauthorized {});
     }
+    if state.funded {
+        return Err(ContractError::AlreadyFunded {});
+    }
+
+    // ensure not expired
+    if state.expires.is_expired(&env.block) {
+        return Err(ContractError::OptionExpired {
+            expired: state.expires,
+        });
+    }
 
-    // set new owner on state
-    state.owner = deps.api.addr_validate(&recipient)?;
+    // ensure sending proper collateral
+    if tokens != state.collateral {
+        return Err(ContractError::CollateralMismatch {
+            sent: tokens,
+            collateral: state.collateral,
+        });
+    }
+
+    state.funded = true;
     CONFIG.save(deps.storage, &state)?;
 
+    Ok(Response::new().add_attribute("action", "fund"))
+}
+
+pub fn execute_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    let owner = deps.api.addr_validate(&recipient)?;
+    transfer_option(deps, &env, &info.sender, owner)?;
+
     let res =
         Response::new().add_attributes([("action", "transfer"), ("owner", recipient.as_str())]);
     Ok(res)
 }
 
-pub fn execute_execute(
+pub fn execute_transfer_nft(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    recipient: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    check_token_id(&token_id)?;
+    let owner = deps.api.addr_validate(&recipient)?;
+    transfer_option(deps, &env, &info.sender, owner)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "transfer_nft"),
+        ("sender", info.sender.as_str()),
+        ("recipient", recipient.as_str()),
+        ("token_id", token_id.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_send_nft(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: String,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    check_token_id(&token_id)?;
+    let owner = deps.api.addr_validate(&contract)?;
+    transfer_option(deps, &env, &info.sender, owner)?;
+
+    // notify the receiving contract, like any cw721 contract does
+    let send = Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id: token_id.clone(),
+        msg,
+    };
+    let msg: CosmosMsg = send.into_cosmos_msg(contract.clone())?;
+    let res = Response::new().add_message(msg).add_attributes([
+        ("action", "send_nft"),
+        ("sender", info.sender.as_str()),
+        ("recipient", contract.as_str()),
+        ("token_id", token_id.as_str()),
+    ]);
+    Ok(res)
+}
+
+// moves the option to a new owner, this is allowed for the owner, approved spenders and operators
+fn transfer_option(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    owner: Addr,
+) -> Result<(), ContractError> {
+    let state = CONFIG.load(deps.storage)?;
+    let approved = matches!(
+        APPROVALS.may_load(deps.storage, sender)?,
+        Some(expires) if !expires.is_expired(&env.block)
+    );
+    if !approved && !can_approve(deps.storage, &env.block, sender, &state)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    set_owner(deps.storage, state, owner)?;
+    Ok(())
+}
+
+fn set_owner(storage: &mut dyn Storage, mut state: State, owner: Addr) -> StdResult<()> {
+    state.owner = owner;
+    CONFIG.save(storage, &state)?;
+
+    // approvals and offers were made by the previous owner
+    let spenders = APPROVALS
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for spender in spenders {
+        APPROVALS.remove(storage, &spender);
+    }
+    OFFER.remove(storage);
+    Ok(())
+}
+
+// the owner and their operators can transfer and approve the option
+fn can_approve(
+    storage: &dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    state: &State,
+) -> StdResult<bool> {
+    if sender == &state.owner {
+        return Ok(true);
+    }
+    let operator = OPERATORS.may_load(storage, (&state.owner, sender))?;
+    Ok(matches!(operator, Some(expires) if !expires.is_expired(block)))
+}
+
+fn check_token_id(token_id: &str) -> StdResult<()> {
+    if token_id != TOKEN_ID {
+        return Err(StdError::not_found(format!("token {}", token_id)));
+    }
+    Ok(())
+}
+
+pub fn execute_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    price: Vec<Coin>,
 ) -> Result<Response, ContractError> {
-    // ensure msg sender is the owner
     let state = CONFIG.load(deps.storage)?;
     if info.sender != state.owner {
         return Err(ContractError::Unauthorized {});
     }
+    if state.expires.is_expired(&env.block) {
+        return Err(ContractError::OptionExpired {
+            expired: state.expires,
+        });
+    }
+    // an option without collateral is worthless, it cannot be sold
+    if !state.funded {
+        return Err(ContractError::NotFunded {});
+    }
+
+    let price = normalize(price);
+    if price.is_empty() {
+        return Err(ContractError::EmptyPrice {});
+    }
+    let offer = Offer {
+        seller: info.sender,
+        price,
+    };
+    OFFER.save(deps.storage, &offer)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "offer"),
+        ("seller", offer.seller.as_str()),
+        ("price", NativeBalance(offer.price).to_string().as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_cancel_offer(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    let offer = OFFER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOffer {})?;
+    if info.sender != offer.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    OFFER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "cancel_offer"))
+}
+
+pub fn execute_buy(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
+    let offer = OFFER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoOffer {})?;
+    let state = CONFIG.load(deps.storage)?;
+    if state.expires.is_expired(&env.block) {
+        return Err(ContractError::OptionExpired {
+            expired: state.expires,
+        });
+    }
+    if !state.funded {
+        return Err(ContractError::NotFunded {});
+    }
+
+    let sent = normalize(info.funds);
+    if sent != offer.price {
+        return Err(ContractError::PriceMismatch {
+            sent,
+            price: offer.price,
+        });
+    }
+
+    // ownership and premium change hands atomically
+    set_owner(deps.storage, state, info.sender.clone())?;
+    let res = Response::new()
+        .add_message(BankMsg::Send {
+            to_address: offer.seller.to_string(),
+            amount: offer.price,
+        })
+        .add_attributes([
+            ("action", "buy"),
+            ("seller", offer.seller.as_str()),
+            ("buyer", info.sender.as_str()),
+        ]);
+    Ok(res)
+}
+
+// sorts coins by denom and merges duplicates, so prices compare regardless of order
+fn normalize(coins: Vec<Coin>) -> Vec<Coin> {
+    let mut balance = NativeBalance(coins);
+    balance.normalize();
+    balance.into_vec()
+}
+
+pub fn execute_approve(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    check_token_id(&token_id)?;
+    let state = CONFIG.load(deps.storage)?;
+    if !can_approve(deps.storage, &env.block, &info.sender, &state)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::ApprovalExpired {});
+    }
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    APPROVALS.save(deps.storage, &spender_addr, &expires)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "approve"),
+        ("sender", info.sender.as_str()),
+        ("spender", spender.as_str()),
+        ("token_id", token_id.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_revoke(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    check_token_id(&token_id)?;
+    let state = CONFIG.load(deps.storage)?;
+    if !can_approve(deps.storage, &env.block, &info.sender, &state)? {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    APPROVALS.remove(deps.storage, &spender_addr);
+
+    let res = Response::new().add_attributes([
+        ("action", "revoke"),
+        ("sender", info.sender.as_str()),
+        ("spender", spender.as_str()),
+        ("token_id", token_id.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_approve_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::ApprovalExpired {});
+    }
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    OPERATORS.save(deps.storage, (&info.sender, &operator_addr), &expires)?;
+
+    let res = Response::new().add_attributes([
+        ("action", "approve_all"),
+        ("sender", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_revoke_all(
+    deps: DepsMut,
+    info: MessageInfo,
+    operator: String,
+) -> Result<Response, ContractError> {
+    let operator_addr = deps.api.addr_validate(&operator)?;
+    OPERATORS.remove(deps.storage, (&info.sender, &operator_addr));
+
+    let res = Response::new().add_attributes([
+        ("action", "revoke_all"),
+        ("sender", info.sender.as_str()),
+        ("operator", operator.as_str()),
+    ]);
+    Ok(res)
+}
+
+pub fn execute_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    units: Option<u64>,
+) -> Result<Response, ContractError> {
+    exercise(deps, env, info.sender, Balance::from(info.funds), units)
+}
+
+// swaps the counter_offer sent by the owner for the collateral, for some or all units
+fn exercise(
+    deps: DepsMut,
+    env: Env,
+    sender: Addr,
+    offer: Balance,
+    units: Option<u64>,
+) -> Result<Response, ContractError> {
+    // ensure msg sender is the owner
+    let mut state = CONFIG.load(deps.storage)?;
+    if sender != state.owner {
+        return Err(ContractError::Unauthorized {});
+    }
 
     // ensure not expired
-    if env.block.height >= state.expires {
+    if state.expires.is_expired(&env.block) {
         return Err(ContractError::OptionExpired {
             expired: state.expires,
         });
     }
 
+    if let ExerciseStyle::European { exercise_start } = state.style {
+        if !state.style.is_open(&env.block) {
+            return Err(ContractError::ExerciseWindowNotOpen {
+                opens: exercise_start,
+            });
+        }
+    }
+
+    if !state.funded {
+        return Err(ContractError::NotFunded {});
+    }
+
+    let units = units.unwrap_or(state.units_remaining);
+    if units == 0 || units > state.units_remaining {
+        return Err(ContractError::InsufficientUnits {
+            requested: units,
+            remaining: state.units_remaining,
+        });
+    }
+
     // ensure sending proper counter_offer
-    if info.funds != state.counter_offer {
+    let counter_offer = share(&state.counter_offer, units, state.units);
+    if offer != counter_offer {
         return Err(ContractError::CounterOfferMismatch {
-            offer: info.funds,
-            counter_offer: state.counter_offer,
+            offer,
+            counter_offer,
         });
     }
 
-    // release counter_offer to creator
     let mut res = Response::new();
-    res = res.add_message(BankMsg::Send {
-        to_address: state.creator.to_string(),
-        amount: state.counter_offer,
-    });
+    let collateral = share(&state.collateral, units, state.units);
+    match &state.settlement {
+        Settlement::Physical => {
+            // release counter_offer to creator
+            res = res.add_message(send_tokens(&state.creator, counter_offer)?);
 
-    // release collateral to sender
-    res = res.add_message(BankMsg::Send {
-        to_address: state.owner.to_string(),
-        amount: state.collateral,
-    });
+            // release collateral to sender
+            res = res.add_message(send_tokens(&state.owner, collateral)?);
+        }
+        Settlement::Cash {
+            oracle,
+            base,
+            quote,
+            strike,
+            size,
+        } => {
+            let query = OracleQueryMsg::Price {
+                base: base.clone(),
+                quote: quote.clone(),
+            };
+            let spot = deps
+                .querier
+                .query_wasm_smart::<PriceResponse>(oracle, &query)?
+                .price;
+            let intrinsic = match state.kind {
+                OptionKind::Call if spot > *strike => spot - *strike,
+                OptionKind::Put if *strike > spot => *strike - spot,
+                _ => Decimal::zero(),
+            };
+            let payout = (*size * intrinsic).multiply_ratio(units, state.units);
 
-    // delete the option
-    CONFIG.remove(deps.storage);
+            // pay the owner out of the collateral and return the rest to the creator
+            let (payout, rest) = split(&collateral, payout);
+            if !payout.is_empty() {
+                res = res.add_message(send_tokens(&state.owner, payout)?);
+            }
+            if !rest.is_empty() {
+                res = res.add_message(send_tokens(&state.creator, rest)?);
+            }
+            res = res.add_attribute("spot", spot.to_string());
+        }
+    }
+
+    // delete the option once all units are executed
+    state.units_remaining -= units;
+    if state.units_remaining == 0 {
+        CONFIG.remove(deps.storage);
+        OFFER.remove(deps.storage);
+    } else {
+        CONFIG.save(deps.storage, &state)?;
+    }
 
-    res = res.add_attribute("action", "execute");
+    res = res.add_attributes([
+        ("action", "execute"),
+        ("units", units.to_string().as_str()),
+        (
+            "units_remaining",
+            state.units_remaining.to_string().as_str(),
+        ),
+    ]);
     Ok(res)
 }
 
 pub fn execute_burn(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Response, ContractError> {
     // ensure is expired
     let state = CONFIG.load(deps.storage)?;
-    if env.block.height < state.expires {
+    if !state.expires.is_expired(&env.block) {
         return Err(ContractError::OptionNotExpired {
             expires: state.expires,
         });
@@ -127,24 +733,110 @@ pub fn execute_burn(deps: DepsMut, env: Env, info: MessageInfo) -> Result<Respon
         return Err(ContractError::FundsSentWithBurn {});
     }
 
-    // release collateral to creator
+    // release collateral of the remaining units to creator, if it was ever sent
     let mut res = Response::new();
-    res = res.add_message(BankMsg::Send {
-        to_address: state.creator.to_string(),
-        amount: state.collateral,
-    });
+    if state.funded {
+        let collateral = share(&state.collateral, state.units_remaining, state.units);
+        res = res.add_message(send_tokens(&state.creator, collateral)?);
+    }
 
     // delete the option
     CONFIG.remove(deps.storage);
+    OFFER.remove(deps.storage);
 
     res = res.add_attribute("action", "burn");
     Ok(res)
 }
 
+// splits a single denom balance into `amount`, at most all of it, and the rest
+fn split(balance: &Balance, amount: Uint128) -> (Balance, Balance) {
+    match balance {
+        Balance::Native(coins) => {
+            let coin = &coins.0[0];
+            let amount = amount.min(coin.amount);
+            (
+                Balance::from(vec![Coin::new(amount.u128(), &coin.denom)]),
+                Balance::from(vec![Coin::new((coin.amount - amount).u128(), &coin.denom)]),
+            )
+        }
+        Balance::Cw20(token) => {
+            let amount = amount.min(token.amount);
+            (
+                Balance::Cw20(Cw20CoinVerified {
+                    address: token.address.clone(),
+                    amount,
+                }),
+                Balance::Cw20(Cw20CoinVerified {
+                    address: token.address.clone(),
+                    amount: token.amount - amount,
+                }),
+            )
+        }
+    }
+}
+
+// moves native coins with a bank send and cw20 tokens with a transfer
+fn send_tokens(to: &Addr, amount: Balance) -> StdResult<CosmosMsg> {
+    match amount {
+        Balance::Native(coins) => Ok(BankMsg::Send {
+            to_address: to.to_string(),
+            amount: coins.into_vec(),
+        }
+        .into()),
+        Balance::Cw20(token) => Cw20Contract(token.address).call(Cw20ExecuteMsg::Transfer {
+            recipient: to.to_string(),
+            amount: token.amount,
+        }),
+    }
+}
+
 #[entry_point]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::Units {} => to_binary(&query_units(deps)?),
+        QueryMsg::Offers {} => to_binary(&query_offers(deps)?),
+        QueryMsg::Terms {} => to_binary(&query_terms(deps, env)?),
+        QueryMsg::OwnerOf {
+            token_id,
+            include_expired,
+        } => to_binary(&query_owner_of(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Approval {
+            token_id,
+            spender,
+            include_expired,
+        } => to_binary(&query_approval(
+            deps,
+            env,
+            token_id,
+            spender,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::NumTokens {} => to_binary(&query_num_tokens(deps)?),
+        QueryMsg::ContractInfo {} => to_binary(&query_contract_info()),
+        QueryMsg::NftInfo { token_id } => to_binary(&query_nft_info(deps, token_id)?),
+        QueryMsg::AllNftInfo {
+            token_id,
+            include_expired,
+        } => to_binary(&query_all_nft_info(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_tokens(deps, Some(owner), start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_binary(&query_tokens(deps, None, start_after, limit)?)
+        }
     }
 }
 
@@ -153,34 +845,185 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     Ok(state)
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{attr, coins, CosmosMsg};
-
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
+fn query_units(deps: Deps) -> StdResult<UnitsResponse> {
+    let state = CONFIG.load(deps.storage)?;
+    Ok(UnitsResponse {
+        units: state.units,
+        units_remaining: state.units_remaining,
+        collateral_per_unit: share(&state.collateral, 1, state.units),
+        counter_offer_per_unit: share(&state.counter_offer, 1, state.units),
+    })
+}
 
-        let msg = InstantiateMsg {
-            counter_offer: coins(40, "ETH"),
-            expires: 100_000,
-        };
-        let info = mock_info("creator", &coins(1, "BTC"));
+fn query_offers(deps: Deps) -> StdResult<OffersResponse> {
+    let offers = OFFER.may_load(deps.storage)?.into_iter().collect();
+    Ok(OffersResponse { offers })
+}
 
-        // we can just call .unwrap() to assert this was a success
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
-        assert_eq!(0, res.messages.len());
+fn query_terms(deps: Deps, env: Env) -> StdResult<TermsResponse> {
+    let state = CONFIG.load(deps.storage)?;
+    let exercise_start = match state.style {
+        ExerciseStyle::American => None,
+        ExerciseStyle::European { exercise_start } => Some(exercise_start),
+    };
+    Ok(TermsResponse {
+        exercisable: state.style.is_open(&env.block) && !state.expires.is_expired(&env.block),
+        kind: state.kind,
+        style: state.style,
+        settlement: state.settlement,
+        exercise_start,
+        expires: state.expires,
+    })
+}
 
-        // it worked, let's query the state
-        let res = query_config(deps.as_ref()).unwrap();
-        assert_eq!(100_000, res.expires);
-        assert_eq!("creator", res.owner.as_str());
-        assert_eq!("creator", res.creator.as_str());
-        assert_eq!(coins(1, "BTC"), res.collateral);
-        assert_eq!(coins(40, "ETH"), res.counter_offer);
-    }
+fn query_owner_of(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    check_token_id(&token_id)?;
+    let state = CONFIG.load(deps.storage)?;
+    let approvals = APPROVALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| match item {
+            Ok((_, expires)) => include_expired || !expires.is_expired(&env.block),
+            Err(_) => true,
+        })
+        .map(|item| {
+            item.map(|(spender, expires)| Approval {
+                spender: spender.to_string(),
+                expires,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(OwnerOfResponse {
+        owner: state.owner.to_string(),
+        approvals,
+    })
+}
+
+fn query_approval(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    spender: String,
+    include_expired: bool,
+) -> StdResult<ApprovalResponse> {
+    check_token_id(&token_id)?;
+    let spender_addr = deps.api.addr_validate(&spender)?;
+    match APPROVALS.may_load(deps.storage, &spender_addr)? {
+        Some(expires) if include_expired || !expires.is_expired(&env.block) => {
+            Ok(ApprovalResponse {
+                approval: Approval { spender, expires },
+            })
+        }
+        _ => Err(StdError::not_found("Approval not found")),
+    }
+}
+
+fn query_num_tokens(deps: Deps) -> StdResult<NumTokensResponse> {
+    // the token is gone once the option is fully executed or burned
+    let count = if CONFIG.may_load(deps.storage)?.is_some() {
+        1
+    } else {
+        0
+    };
+    Ok(NumTokensResponse { count })
+}
+
+fn query_contract_info() -> ContractInfoResponse {
+    ContractInfoResponse {
+        name: NFT_NAME.to_string(),
+        symbol: NFT_SYMBOL.to_string(),
+    }
+}
+
+fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse<Empty>> {
+    check_token_id(&token_id)?;
+    // errors if the option no longer exists
+    CONFIG.load(deps.storage)?;
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: Empty {},
+    })
+}
+
+fn query_all_nft_info(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<AllNftInfoResponse<Empty>> {
+    Ok(AllNftInfoResponse {
+        access: query_owner_of(deps, env, token_id.clone(), include_expired)?,
+        info: query_nft_info(deps, token_id)?,
+    })
+}
+
+fn query_tokens(
+    deps: Deps,
+    owner: Option<String>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let state = CONFIG.may_load(deps.storage)?;
+    let owned = match (&state, owner) {
+        (Some(state), Some(owner)) => state.owner == deps.api.addr_validate(&owner)?,
+        (Some(_), None) => true,
+        (None, _) => false,
+    };
+    // there is at most one token, so a page either holds it or is empty
+    let after_start = !matches!(start_after, Some(start) if start.as_str() >= TOKEN_ID);
+    let tokens = if owned && after_start && limit != Some(0) {
+        vec![TOKEN_ID.to_string()]
+    } else {
+        vec![]
+    };
+    Ok(TokensResponse { tokens })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::CONFIG_KEY;
+    use cosmwasm_std::testing::{
+        mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{
+        attr, coin, coins, Addr, CosmosMsg, OwnedDeps, SystemError, SystemResult, Uint128, WasmMsg,
+        WasmQuery,
+    };
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+
+        // we can just call .unwrap() to assert this was a success
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        // it worked, let's query the state
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!(Expiration::AtHeight(100_000), res.expires);
+        assert_eq!("creator", res.owner.as_str());
+        assert_eq!("creator", res.creator.as_str());
+        assert_eq!(Balance::from(coins(1, "BTC")), res.collateral);
+        assert_eq!(Balance::from(coins(40, "ETH")), res.counter_offer);
+        assert!(res.funded);
+    }
 
     #[test]
     fn transfer() {
@@ -188,7 +1031,13 @@ mod tests {
 
         let msg = InstantiateMsg {
             counter_offer: coins(40, "ETH"),
-            expires: 100_000,
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
         };
         let info = mock_info("creator", &coins(1, "BTC"));
 
@@ -223,10 +1072,16 @@ mod tests {
 
         let amount = coins(40, "ETH");
         let collateral = coins(1, "BTC");
-        let expires = 100_000;
+        let expires = Expiration::AtHeight(100_000);
         let msg = InstantiateMsg {
             counter_offer: amount.clone(),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
             expires,
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
         };
         let info = mock_info("creator", &collateral);
 
@@ -239,7 +1094,7 @@ mod tests {
 
         // random cannot execute
         let info = mock_info("creator", &amount);
-        let err = execute_execute(deps.as_mut(), mock_env(), info).unwrap_err();
+        let err = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap_err();
         match err {
             ContractError::Unauthorized {} => {}
             e => panic!("unexpected error: {}", e),
@@ -249,7 +1104,7 @@ mod tests {
         let info = mock_info("owner", &amount);
         let mut env = mock_env();
         env.block.height = 200_000;
-        let err = execute_execute(deps.as_mut(), env, info).unwrap_err();
+        let err = execute_execute(deps.as_mut(), env, info, None).unwrap_err();
         match err {
             ContractError::OptionExpired { expired } => assert_eq!(expired, expires),
             e => panic!("unexpected error: {}", e),
@@ -258,21 +1113,21 @@ mod tests {
         // bad counter_offer cannot execute
         let msg_offer = coins(39, "ETH");
         let info = mock_info("owner", &msg_offer);
-        let err = execute_execute(deps.as_mut(), mock_env(), info).unwrap_err();
+        let err = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap_err();
         match err {
             ContractError::CounterOfferMismatch {
                 offer,
                 counter_offer,
             } => {
-                assert_eq!(msg_offer, offer);
-                assert_eq!(amount, counter_offer);
+                assert_eq!(Balance::from(msg_offer), offer);
+                assert_eq!(Balance::from(amount.clone()), counter_offer);
             }
             e => panic!("unexpected error: {}", e),
         }
 
         // proper execution
         let info = mock_info("owner", &amount);
-        let res = execute_execute(deps.as_mut(), mock_env(), info).unwrap();
+        let res = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap();
         assert_eq!(res.messages.len(), 2);
         assert_eq!(
             res.messages[0].msg,
@@ -299,10 +1154,16 @@ mod tests {
 
         let counter_offer = coins(40, "ETH");
         let collateral = coins(1, "BTC");
-        let msg_expires = 100_000;
+        let msg_expires = Expiration::AtHeight(100_000);
         let msg = InstantiateMsg {
             counter_offer: counter_offer.clone(),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
             expires: msg_expires,
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
         };
         let info = mock_info("creator", &collateral);
 
@@ -348,4 +1209,958 @@ mod tests {
         // check deleted
         let _ = query_config(deps.as_ref()).unwrap_err();
     }
+
+    #[test]
+    fn cw20_collateral_and_counter_offer() {
+        let mut deps = mock_dependencies();
+
+        // cannot mix native and cw20 collateral
+        let mut msg = InstantiateMsg {
+            counter_offer: vec![],
+            cw20_counter_offer: Some(Cw20Coin {
+                address: "usdc".to_string(),
+                amount: Uint128::new(40),
+            }),
+            cw20_collateral: Some(Cw20Coin {
+                address: "wbtc".to_string(),
+                amount: Uint128::new(1),
+            }),
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+        let err = instantiate(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        match err {
+            ContractError::MixedAssets {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        msg.counter_offer = coins(40, "ETH");
+        let info = mock_info("creator", &[]);
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::MixedAssets {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        msg.counter_offer = vec![];
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let state = query_config(deps.as_ref()).unwrap();
+        assert!(!state.funded);
+
+        let info = mock_info("creator", &[]);
+        execute_transfer(deps.as_mut(), mock_env(), info, "owner".to_string()).unwrap();
+
+        // cannot execute before the collateral arrived
+        let exercise_msg = |amount: u128| Cw20ReceiveMsg {
+            sender: "owner".to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::Execute { units: None }).unwrap(),
+        };
+        let info = mock_info("usdc", &[]);
+        let err =
+            execute_receive(deps.as_mut(), mock_env(), info.clone(), exercise_msg(40)).unwrap_err();
+        match err {
+            ContractError::NotFunded {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // nor sell it
+        let owner = mock_info("owner", &[]);
+        let err = execute_offer(deps.as_mut(), mock_env(), owner, coins(5, "ATOM")).unwrap_err();
+        match err {
+            ContractError::NotFunded {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let offer = Offer {
+            seller: Addr::unchecked("owner"),
+            price: coins(5, "ATOM"),
+        };
+        OFFER.save(&mut deps.storage, &offer).unwrap();
+        let buyer = mock_info("buyer", &coins(5, "ATOM"));
+        let err = execute_buy(deps.as_mut(), mock_env(), buyer).unwrap_err();
+        match err {
+            ContractError::NotFunded {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        OFFER.remove(&mut deps.storage);
+
+        // only the creator can fund, with exactly the collateral token and amount
+        let fund_msg = |sender: &str, amount: u128| Cw20ReceiveMsg {
+            sender: sender.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_binary(&ReceiveMsg::Fund {}).unwrap(),
+        };
+        let err = execute_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("wbtc", &[]),
+            fund_msg("owner", 1),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err = execute_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("fake", &[]),
+            fund_msg("creator", 1),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::CollateralMismatch { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let res = execute_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("wbtc", &[]),
+            fund_msg("creator", 1),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "fund"));
+        let err = execute_receive(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("wbtc", &[]),
+            fund_msg("creator", 1),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::AlreadyFunded {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // native coins are no valid counter_offer
+        let err = execute_execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &coins(40, "ETH")),
+            None,
+        )
+        .unwrap_err();
+        match err {
+            ContractError::CounterOfferMismatch { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        let err =
+            execute_receive(deps.as_mut(), mock_env(), info.clone(), exercise_msg(39)).unwrap_err();
+        match err {
+            ContractError::CounterOfferMismatch { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // both legs are paid out as cw20 transfers
+        let res = execute_receive(deps.as_mut(), mock_env(), info, exercise_msg(40)).unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "usdc".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "creator".into(),
+                    amount: Uint128::new(40),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "wbtc".into(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: "owner".into(),
+                    amount: Uint128::new(1),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // check deleted
+        let _ = query_config(deps.as_ref()).unwrap_err();
+    }
+
+    #[test]
+    fn burn_unfunded() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: Some(Cw20Coin {
+                address: "wbtc".to_string(),
+                amount: Uint128::new(1),
+            }),
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // nothing to return if the collateral was never sent
+        let info = mock_info("anyone", &[]);
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let res = execute_burn(deps.as_mut(), env, info).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        let _ = query_config(deps.as_ref()).unwrap_err();
+    }
+
+    #[test]
+    fn time_based_expiry() {
+        let mut deps = mock_dependencies();
+
+        let expires = Expiration::AtTime(mock_env().block.time.plus_seconds(3600));
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires,
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the block height does not matter
+        let mut env = mock_env();
+        env.block.height += 1_000_000;
+        let info = mock_info("anyone", &[]);
+        let err = execute_burn(deps.as_mut(), env, info).unwrap_err();
+        match err {
+            ContractError::OptionNotExpired { expires: e } => assert_eq!(e, expires),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(3600);
+        let info = mock_info("creator", &coins(40, "ETH"));
+        let err = execute_execute(deps.as_mut(), env.clone(), info, None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "expired option (expired at expiration time: 1571801019.879305533)"
+        );
+
+        let info = mock_info("anyone", &[]);
+        let res = execute_burn(deps.as_mut(), env, info).unwrap();
+        assert_eq!(res.messages.len(), 1);
+    }
+
+    #[test]
+    fn migrate_height_based_state() {
+        let mut deps = mock_dependencies();
+
+        // stored as by the first release
+        deps.storage.set(
+            CONFIG_KEY.as_bytes(),
+            br#"{"creator":"creator","owner":"owner","collateral":[{"denom":"BTC","amount":"1"}],"counter_offer":[{"denom":"ETH","amount":"40"}],"expires":100000}"#,
+        );
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let state = query_config(deps.as_ref()).unwrap();
+        assert_eq!(state.expires, Expiration::AtHeight(100_000));
+        assert_eq!(state.owner, Addr::unchecked("owner"));
+        assert_eq!(state.collateral, Balance::from(coins(1, "BTC")));
+        assert_eq!(state.counter_offer, Balance::from(coins(40, "ETH")));
+        assert!(state.funded);
+        assert_eq!(state.units_remaining, 1);
+
+        // migrating again keeps the state
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        let err = execute_burn(deps.as_mut(), mock_env(), mock_info("anyone", &[])).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "not expired option (expires at expiration height: 100000)"
+        );
+    }
+
+    #[test]
+    fn migrate_unknown_state_fails() {
+        let mut deps = mock_dependencies();
+        deps.storage
+            .set(CONFIG_KEY.as_bytes(), br#"{"creator":"creator"}"#);
+
+        let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+        match err {
+            ContractError::Std(StdError::ParseErr { .. }) => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn partial_execute() {
+        let mut deps = mock_dependencies();
+
+        // every denom has to be divisible by the units
+        let mut msg = InstantiateMsg {
+            counter_offer: vec![coin(40, "ETH"), coin(100, "USDC")],
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: Some(0),
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(4, "BTC"));
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::ZeroUnits {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+        msg.units = Some(8);
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::IndivisibleAmount {
+                denom,
+                amount,
+                units,
+            } => {
+                assert_eq!(denom, "BTC");
+                assert_eq!(amount, Uint128::new(4));
+                assert_eq!(units, 8);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+        msg.units = Some(4);
+        msg.counter_offer = vec![coin(40, "ETH"), coin(102, "USDC")];
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        assert_eq!(err.to_string(), "102USDC cannot be divided into 4 units");
+        msg.counter_offer = vec![coin(40, "ETH"), coin(100, "USDC")];
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let units = query_units(deps.as_ref()).unwrap();
+        assert_eq!(
+            units,
+            UnitsResponse {
+                units: 4,
+                units_remaining: 4,
+                collateral_per_unit: Balance::from(coins(1, "BTC")),
+                counter_offer_per_unit: Balance::from(vec![coin(10, "ETH"), coin(25, "USDC")]),
+            }
+        );
+
+        // paying for all units while executing some is a mismatch
+        let info = mock_info("creator", &[coin(40, "ETH"), coin(100, "USDC")]);
+        let err = execute_execute(deps.as_mut(), mock_env(), info, Some(3)).unwrap_err();
+        match err {
+            ContractError::CounterOfferMismatch { counter_offer, .. } => assert_eq!(
+                counter_offer,
+                Balance::from(vec![coin(30, "ETH"), coin(75, "USDC")])
+            ),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let info = mock_info("creator", &[coin(30, "ETH"), coin(75, "USDC")]);
+        let res = execute_execute(deps.as_mut(), mock_env(), info, Some(3)).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(3, "BTC"),
+            })
+        );
+        assert_eq!(res.attributes[2], attr("units_remaining", "1"));
+        assert_eq!(query_units(deps.as_ref()).unwrap().units_remaining, 1);
+
+        // cannot execute more units than are left
+        let info = mock_info("creator", &[coin(20, "ETH"), coin(50, "USDC")]);
+        let err = execute_execute(deps.as_mut(), mock_env(), info, Some(2)).unwrap_err();
+        match err {
+            ContractError::InsufficientUnits {
+                requested,
+                remaining,
+            } => {
+                assert_eq!(requested, 2);
+                assert_eq!(remaining, 1);
+            }
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // the remaining collateral goes back to the creator once expired
+        let mut env = mock_env();
+        env.block.height = 200_000;
+        let res = execute_burn(deps.as_mut(), env, mock_info("anyone", &[])).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(1, "BTC"),
+            })
+        );
+    }
+
+    #[test]
+    fn execute_remaining_units() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: Some(4),
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(4, "BTC"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("creator", &coins(10, "ETH"));
+        execute_execute(deps.as_mut(), mock_env(), info, Some(1)).unwrap();
+
+        // without units, everything left is executed and the option deleted
+        let info = mock_info("creator", &coins(30, "ETH"));
+        let res = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(3, "BTC"),
+            })
+        );
+        let _ = query_config(deps.as_ref()).unwrap_err();
+    }
+
+    #[test]
+    fn nft_approvals() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // spender cannot transfer before approval
+        let info = mock_info("spender", &[]);
+        let err = execute_transfer_nft(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            "buyer".to_string(),
+            TOKEN_ID.to_string(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // expired approvals are rejected
+        let err = execute_approve(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            "spender".to_string(),
+            TOKEN_ID.to_string(),
+            Some(Expiration::AtHeight(1)),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::ApprovalExpired {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        execute_approve(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            "spender".to_string(),
+            TOKEN_ID.to_string(),
+            None,
+        )
+        .unwrap();
+        let res = query_owner_of(deps.as_ref(), mock_env(), TOKEN_ID.to_string(), false).unwrap();
+        assert_eq!("creator", res.owner);
+        assert_eq!(
+            res.approvals,
+            vec![Approval {
+                spender: "spender".to_string(),
+                expires: Expiration::Never {},
+            }]
+        );
+
+        // approved spender can transfer, which clears the approval
+        let res = execute_transfer_nft(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            "buyer".to_string(),
+            TOKEN_ID.to_string(),
+        )
+        .unwrap();
+        assert_eq!(res.attributes[0], attr("action", "transfer_nft"));
+        let res = query_owner_of(deps.as_ref(), mock_env(), TOKEN_ID.to_string(), false).unwrap();
+        assert_eq!("buyer", res.owner);
+        assert!(res.approvals.is_empty());
+
+        // an operator of the new owner can transfer on their behalf
+        execute_approve_all(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("buyer", &[]),
+            "operator".to_string(),
+            None,
+        )
+        .unwrap();
+        execute_transfer(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("operator", &[]),
+            "holder".to_string(),
+        )
+        .unwrap();
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!("holder", res.owner.as_str());
+
+        // but not once revoked
+        execute_revoke_all(
+            deps.as_mut(),
+            mock_info("buyer", &[]),
+            "operator".to_string(),
+        )
+        .unwrap();
+        let res = query_tokens(deps.as_ref(), Some("holder".to_string()), None, None).unwrap();
+        assert_eq!(res.tokens, vec![TOKEN_ID.to_string()]);
+        let res = query_tokens(deps.as_ref(), Some("buyer".to_string()), None, None).unwrap();
+        assert!(res.tokens.is_empty());
+
+        // the new owner executes the option
+        let info = mock_info("holder", &coins(40, "ETH"));
+        let res = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "holder".into(),
+                amount: coins(1, "BTC"),
+            })
+        );
+        let res = query_num_tokens(deps.as_ref()).unwrap();
+        assert_eq!(0, res.count);
+    }
+
+    #[test]
+    fn send_nft() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // unknown token ids are not found
+        let err = execute_send_nft(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            "market".to_string(),
+            "other".to_string(),
+            Binary::from(b"list".to_vec()),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::Std(StdError::NotFound { .. }) => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let res = execute_send_nft(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            "market".to_string(),
+            TOKEN_ID.to_string(),
+            Binary::from(b"list".to_vec()),
+        )
+        .unwrap();
+        let receive = Cw721ReceiveMsg {
+            sender: "creator".to_string(),
+            token_id: TOKEN_ID.to_string(),
+            msg: Binary::from(b"list".to_vec()),
+        };
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "market".into(),
+                msg: receive.into_binary().unwrap(),
+                funds: vec![],
+            })
+        );
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!("market", res.owner.as_str());
+
+        let res = query_contract_info();
+        assert_eq!("OPTION", res.symbol);
+        let res = query_num_tokens(deps.as_ref()).unwrap();
+        assert_eq!(1, res.count);
+    }
+
+    #[test]
+    fn european_put() {
+        let mut deps = mock_dependencies();
+
+        // the window must start before expiry
+        let msg = InstantiateMsg {
+            counter_offer: coins(1, "BTC"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Put,
+            style: ExerciseStyle::European {
+                exercise_start: Expiration::AtHeight(100_000),
+            },
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(40_000, "USD"));
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::InvalidExerciseWindow { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // heights and times cannot be compared
+        let msg = InstantiateMsg {
+            style: ExerciseStyle::European {
+                exercise_start: Expiration::AtTime(mock_env().block.time.plus_seconds(60)),
+            },
+            ..msg
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::InvalidExerciseWindow { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // a put locks the strike and asks for the underlying
+        let exercise_start = Expiration::AtHeight(90_000);
+        let msg = InstantiateMsg {
+            style: ExerciseStyle::European { exercise_start },
+            ..msg
+        };
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query_terms(deps.as_ref(), mock_env()).unwrap();
+        assert_eq!(OptionKind::Put, res.kind);
+        assert_eq!(Some(exercise_start), res.exercise_start);
+        assert!(!res.exercisable);
+
+        // cannot execute before the window opens
+        let info = mock_info("creator", &coins(1, "BTC"));
+        let err = execute_execute(deps.as_mut(), mock_env(), info.clone(), None).unwrap_err();
+        match err {
+            ContractError::ExerciseWindowNotOpen { opens } => assert_eq!(opens, exercise_start),
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // but inside of it
+        let mut env = mock_env();
+        env.block.height = 90_000;
+        let res = query_terms(deps.as_ref(), env.clone()).unwrap();
+        assert!(res.exercisable);
+        let res = execute_execute(deps.as_mut(), env, info, None).unwrap();
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(40_000, "USD"),
+            })
+        );
+    }
+
+    #[test]
+    fn offer_and_buy() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: None,
+        };
+        let info = mock_info("creator", &coins(1, "BTC"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // nothing to buy yet
+        let info = mock_info("buyer", &coins(5, "USD"));
+        let err = execute_buy(deps.as_mut(), mock_env(), info).unwrap_err();
+        match err {
+            ContractError::NoOffer {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // only the owner can offer
+        let info = mock_info("anyone", &[]);
+        let err = execute_offer(deps.as_mut(), mock_env(), info, coins(5, "USD")).unwrap_err();
+        match err {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        let info = mock_info("creator", &[]);
+        execute_offer(deps.as_mut(), mock_env(), info, coins(5, "USD")).unwrap();
+        let res = query_offers(deps.as_ref()).unwrap();
+        assert_eq!(
+            res.offers,
+            vec![Offer {
+                seller: Addr::unchecked("creator"),
+                price: coins(5, "USD"),
+            }]
+        );
+
+        // the exact price has to be paid
+        let info = mock_info("buyer", &coins(4, "USD"));
+        let err = execute_buy(deps.as_mut(), mock_env(), info).unwrap_err();
+        match err {
+            ContractError::PriceMismatch { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // premium goes to the seller, the option to the buyer
+        let info = mock_info("buyer", &coins(5, "USD"));
+        let res = execute_buy(deps.as_mut(), mock_env(), info).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(5, "USD"),
+            })
+        );
+        let res = query_config(deps.as_ref()).unwrap();
+        assert_eq!("buyer", res.owner.as_str());
+        let res = query_offers(deps.as_ref()).unwrap();
+        assert!(res.offers.is_empty());
+
+        // a transfer cancels the offer
+        let info = mock_info("buyer", &[]);
+        execute_offer(deps.as_mut(), mock_env(), info.clone(), coins(8, "USD")).unwrap();
+        execute_transfer(deps.as_mut(), mock_env(), info, "friend".to_string()).unwrap();
+        let res = query_offers(deps.as_ref()).unwrap();
+        assert!(res.offers.is_empty());
+
+        // the seller can also cancel it
+        let info = mock_info("friend", &[]);
+        execute_offer(deps.as_mut(), mock_env(), info.clone(), coins(8, "USD")).unwrap();
+        execute_cancel_offer(deps.as_mut(), info).unwrap();
+        let info = mock_info("buyer", &coins(8, "USD"));
+        let err = execute_buy(deps.as_mut(), mock_env(), info).unwrap_err();
+        match err {
+            ContractError::NoOffer {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+    }
+
+    // answers queries to "oracle" with a mock-oracle contract quoting BTC at `price` USD
+    fn mock_price(deps: &mut OwnedDeps<MockStorage, MockApi, MockQuerier>, price: Decimal) {
+        let mut oracle = mock_dependencies();
+        let info = mock_info("oracle_owner", &[]);
+        let msg = mock_oracle::msg::InstantiateMsg {};
+        mock_oracle::contract::instantiate(oracle.as_mut(), mock_env(), info.clone(), msg).unwrap();
+        mock_oracle::contract::execute_set_price(
+            oracle.as_mut(),
+            info,
+            "BTC".to_string(),
+            "USD".to_string(),
+            price,
+        )
+        .unwrap();
+
+        deps.querier.update_wasm(move |query| match query {
+            WasmQuery::Smart { contract_addr, msg } if contract_addr == "oracle" => {
+                let msg = from_binary(msg).unwrap();
+                SystemResult::Ok(
+                    mock_oracle::contract::query(oracle.as_ref(), mock_env(), msg).into(),
+                )
+            }
+            _ => SystemResult::Err(SystemError::UnsupportedRequest {
+                kind: "unknown contract".to_string(),
+            }),
+        });
+    }
+
+    #[test]
+    fn cash_settlement() {
+        let mut deps = mock_dependencies();
+
+        let cash = CashSettlementMsg {
+            oracle: "oracle".to_string(),
+            base: "BTC".to_string(),
+            quote: "USD".to_string(),
+            strike: Decimal::from_ratio(5u128, 1u128),
+            size: Uint128::new(10),
+        };
+        let msg = InstantiateMsg {
+            counter_offer: coins(40, "ETH"),
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: Some(2),
+            kind: OptionKind::Call,
+            style: ExerciseStyle::American,
+            cash_settlement: Some(cash),
+        };
+
+        // nothing is paid to execute
+        let info = mock_info("creator", &coins(100, "USD"));
+        let err = instantiate(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap_err();
+        match err {
+            ContractError::CashSettledCounterOffer {} => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // and paid out in a single denom
+        let msg = InstantiateMsg {
+            counter_offer: vec![],
+            ..msg
+        };
+        let funds = vec![coin(100, "USD"), coin(100, "EUR")];
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &funds),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::CashSettledCollateral { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        // of the quote denom the payout is computed in
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &coins(100, "EUR")),
+            msg.clone(),
+        )
+        .unwrap_err();
+        match err {
+            ContractError::CashSettledCollateral { quote } => assert_eq!(quote, "USD"),
+            e => panic!("unexpected error: {}", e),
+        }
+        let cw20_msg = InstantiateMsg {
+            cw20_collateral: Some(Cw20Coin {
+                address: "usd_token".to_string(),
+                amount: Uint128::new(100),
+            }),
+            ..msg.clone()
+        };
+        let err = instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("creator", &[]),
+            cw20_msg,
+        )
+        .unwrap_err();
+        match err {
+            ContractError::CashSettledCollateral { .. } => {}
+            e => panic!("unexpected error: {}", e),
+        }
+
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // (8 - 5) * 10 = 30 for both units, the owner gets 15 of the 50 collateral of one unit
+        mock_price(&mut deps, Decimal::from_ratio(8u128, 1u128));
+        let info = mock_info("creator", &[]);
+        let res = execute_execute(deps.as_mut(), mock_env(), info.clone(), Some(1)).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(15, "USD"),
+            })
+        );
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(35, "USD"),
+            })
+        );
+
+        // the payout is capped at the collateral
+        execute_transfer(deps.as_mut(), mock_env(), info, "owner".to_string()).unwrap();
+        mock_price(&mut deps, Decimal::from_ratio(20u128, 1u128));
+        let info = mock_info("owner", &[]);
+        let res = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "owner".into(),
+                amount: coins(50, "USD"),
+            })
+        );
+    }
+
+    #[test]
+    fn cash_settled_put_out_of_the_money() {
+        let mut deps = mock_dependencies();
+
+        let msg = InstantiateMsg {
+            counter_offer: vec![],
+            cw20_counter_offer: None,
+            cw20_collateral: None,
+            expires: Expiration::AtHeight(100_000),
+            units: None,
+            kind: OptionKind::Put,
+            style: ExerciseStyle::American,
+            cash_settlement: Some(CashSettlementMsg {
+                oracle: "oracle".to_string(),
+                base: "BTC".to_string(),
+                quote: "USD".to_string(),
+                strike: Decimal::from_ratio(5u128, 1u128),
+                size: Uint128::new(10),
+            }),
+        };
+        let info = mock_info("creator", &coins(50, "USD"));
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // the spot is above the strike, so all collateral goes back to the creator
+        mock_price(&mut deps, Decimal::from_ratio(6u128, 1u128));
+        let info = mock_info("creator", &[]);
+        execute_transfer(deps.as_mut(), mock_env(), info, "owner".to_string()).unwrap();
+        let info = mock_info("owner", &[]);
+        let res = execute_execute(deps.as_mut(), mock_env(), info, None).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: "creator".into(),
+                amount: coins(50, "USD"),
+            })
+        );
+        assert!(res.attributes.contains(&attr("spot", "6")));
+    }
 }
@@ -1,4 +1,6 @@
-use cosmwasm_std::{Coin, StdError};
+use cosmwasm_std::{Coin, StdError, Uint128};
+use cw20::Balance;
+use cw_utils::Expiration;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -6,21 +8,73 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
-    #[error("expired option (expired {expired:?})")]
-    OptionExpired { expired: u64 },
+    #[error("expired option (expired at {expired})")]
+    OptionExpired { expired: Expiration },
 
-    #[error("not expired option (expires {expires:?})")]
-    OptionNotExpired { expires: u64 },
+    #[error("not expired option (expires at {expires})")]
+    OptionNotExpired { expires: Expiration },
 
     #[error("unauthorized")]
     Unauthorized {},
 
     #[error("must send exact counter offer (offer {offer:?}, counter_offer: {counter_offer:?})")]
     CounterOfferMismatch {
-        offer: Vec<Coin>,
-        counter_offer: Vec<Coin>,
+        offer: Balance,
+        counter_offer: Balance,
     },
 
+    #[error("must send exact collateral (sent {sent:?}, collateral: {collateral:?})")]
+    CollateralMismatch { sent: Balance, collateral: Balance },
+
+    #[error("option legs are either native coins or a single cw20 token, not both")]
+    MixedAssets {},
+
+    #[error("collateral not funded yet")]
+    NotFunded {},
+
+    #[error("collateral already funded")]
+    AlreadyFunded {},
+
+    #[error("option must have at least one unit")]
+    ZeroUnits {},
+
+    #[error("{amount}{denom} cannot be divided into {units} units")]
+    IndivisibleAmount {
+        denom: String,
+        amount: Uint128,
+        units: u64,
+    },
+
+    #[error("cannot execute {requested} units, {remaining} remaining")]
+    InsufficientUnits { requested: u64, remaining: u64 },
+
+    #[error("exercise window must start before the option expires (exercise_start {exercise_start}, expires {expires})")]
+    InvalidExerciseWindow {
+        exercise_start: Expiration,
+        expires: Expiration,
+    },
+
+    #[error("exercise window not open (opens at {opens})")]
+    ExerciseWindowNotOpen { opens: Expiration },
+
+    #[error("offer price must not be empty")]
+    EmptyPrice {},
+
+    #[error("option is not offered for sale")]
+    NoOffer {},
+
+    #[error("must send exact price (sent {sent:?}, price: {price:?})")]
+    PriceMismatch { sent: Vec<Coin>, price: Vec<Coin> },
+
+    #[error("cash settled options have no counter offer")]
+    CashSettledCounterOffer {},
+
+    #[error("cash settled options need native collateral in the quote denom {quote} only")]
+    CashSettledCollateral { quote: String },
+
+    #[error("approval already expired")]
+    ApprovalExpired {},
+
     #[error("do not send funds with burn")]
     FundsSentWithBurn {},
 }
@@ -1,23 +1,124 @@
-use crate::state::State;
+use crate::state::{ExerciseStyle, Offer, OptionKind, Settlement, State};
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::Coin;
+use cosmwasm_std::{Binary, Coin, Decimal, Empty, Uint128};
+use cw20::{Balance, Cw20Coin, Cw20ReceiveMsg};
+use cw721::{
+    AllNftInfoResponse, ApprovalResponse, ContractInfoResponse, NftInfoResponse, NumTokensResponse,
+    OwnerOfResponse, TokensResponse,
+};
+use cw_utils::Expiration;
 
 #[cw_serde]
 pub struct InstantiateMsg {
     // owner and creator come from env
-    // collateral comes from env
+    // native collateral comes from env
+    #[serde(default)]
     pub counter_offer: Vec<Coin>,
-    pub expires: u64,
+    /// Asks for a cw20 token instead of native coins when executing
+    pub cw20_counter_offer: Option<Cw20Coin>,
+    /// Uses a cw20 token as collateral instead of native coins. It has to be
+    /// sent by the creator with a `ReceiveMsg::Fund` before the option can be executed
+    pub cw20_collateral: Option<Cw20Coin>,
+    /// Block height or time at which the option expires
+    pub expires: Expiration,
+    /// Divides the option into units that can be executed separately, defaults to 1.
+    /// The amount of every collateral and counter_offer denom has to be a multiple of it.
+    pub units: Option<u64>,
+    /// Call or put, defaults to a call
+    #[serde(default)]
+    pub kind: OptionKind,
+    /// American or European, defaults to American
+    #[serde(default)]
+    pub style: ExerciseStyle,
+    /// Settles in cash at the oracle price instead of swapping the counter_offer
+    /// for the collateral. Requires an empty counter_offer and a single collateral denom.
+    pub cash_settlement: Option<CashSettlementMsg>,
 }
 
+#[cw_serde]
+pub struct CashSettlementMsg {
+    /// Price oracle contract, implementing `OracleQueryMsg`
+    pub oracle: String,
+    pub base: String,
+    /// Denom the collateral is paid in
+    pub quote: String,
+    /// Price of one `base` in `quote`
+    pub strike: Decimal,
+    /// Amount of `base` the option is written on
+    pub size: Uint128,
+}
+
+#[cw_serde]
+pub struct MigrateMsg {}
+
 #[cw_serde]
 pub enum ExecuteMsg {
-    /// Owner can transfer to a new owner
-    Transfer { recipient: String },
-    /// Owner can post counter_offer on unexpired option to execute and get the collateral
-    Execute {},
+    /// Owner can transfer to a new owner, same as `TransferNft`
+    Transfer {
+        recipient: String,
+    },
+    /// Owner can post counter_offer on unexpired option to execute and get the collateral.
+    /// European options can only be executed once the exercise window opened.
+    /// When executing some `units` only, the same share of the counter_offer is required and
+    /// of the collateral released. Executes all remaining units if not set.
+    Execute {
+        units: Option<u64>,
+    },
     /// Burn will release collateral if expired
     Burn {},
+    /// Receives cw20 tokens, to fund the collateral or to execute with a cw20 counter_offer
+    Receive(Cw20ReceiveMsg),
+    /// Owner puts the option up for sale, replacing any previous offer.
+    /// Transfers cancel the offer.
+    Offer {
+        price: Vec<Coin>,
+    },
+    /// Owner withdraws the option from sale
+    CancelOffer {},
+    /// Anyone can buy an offered option by sending exactly its price,
+    /// which is forwarded to the seller
+    Buy {},
+
+    // The option is the only token of a cw721 collection, with the token id "option".
+    // These follow the cw721 spec, so it can be held and traded like any other NFT.
+    /// Transfers the option to a new owner, callable by the owner, approved spenders and operators
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+    /// Transfers the option to a contract and triggers an action on it
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+    /// Allows a spender to transfer the option, until it changes owner
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+    Revoke {
+        spender: String,
+        token_id: String,
+    },
+    /// Allows an operator to transfer and approve all tokens of the sender
+    ApproveAll {
+        operator: String,
+        expires: Option<Expiration>,
+    },
+    RevokeAll {
+        operator: String,
+    },
+}
+
+#[cw_serde]
+pub enum ReceiveMsg {
+    /// Creator deposits the cw20 collateral
+    Fund {},
+    /// Owner sends the cw20 counter_offer to execute and get the collateral,
+    /// for some `units` only like `ExecuteMsg::Execute`
+    Execute { units: Option<u64> },
 }
 
 #[cw_serde]
@@ -25,7 +126,90 @@ pub enum ExecuteMsg {
 pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
+    /// Returns how many units are left and what a single unit costs and pays out
+    #[returns(UnitsResponse)]
+    Units {},
+    /// Returns the open offers to sell the option, at most one
+    #[returns(OffersResponse)]
+    Offers {},
+    /// Returns the kind of the option and when it can be executed
+    #[returns(TermsResponse)]
+    Terms {},
+
+    // cw721 queries, see `ExecuteMsg::TransferNft`
+    #[returns(OwnerOfResponse)]
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    #[returns(ApprovalResponse)]
+    Approval {
+        token_id: String,
+        spender: String,
+        include_expired: Option<bool>,
+    },
+    #[returns(NumTokensResponse)]
+    NumTokens {},
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+    #[returns(NftInfoResponse<Empty>)]
+    NftInfo { token_id: String },
+    #[returns(AllNftInfoResponse<Empty>)]
+    AllNftInfo {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+    #[returns(TokensResponse)]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    #[returns(TokensResponse)]
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
 }
 
 // We define a custom struct for each query response
 pub type ConfigResponse = State;
+
+#[cw_serde]
+pub struct UnitsResponse {
+    pub units: u64,
+    pub units_remaining: u64,
+    pub collateral_per_unit: Balance,
+    pub counter_offer_per_unit: Balance,
+}
+
+#[cw_serde]
+pub struct OffersResponse {
+    pub offers: Vec<Offer>,
+}
+
+#[cw_serde]
+pub struct TermsResponse {
+    pub kind: OptionKind,
+    pub style: ExerciseStyle,
+    pub settlement: Settlement,
+    /// Start of the exercise window, None for American options
+    pub exercise_start: Option<Expiration>,
+    pub expires: Expiration,
+    /// Whether the option can be executed at the current block
+    pub exercisable: bool,
+}
+
+/// Query interface of the price oracles used for cash settlement
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum OracleQueryMsg {
+    /// Returns the price of one `base` in `quote`
+    #[returns(PriceResponse)]
+    Price { base: String, quote: String },
+}
+
+#[cw_serde]
+pub struct PriceResponse {
+    pub price: Decimal,
+}
@@ -1,10 +1,106 @@
 use cosmwasm_schema::cw_serde;
 
-use cosmwasm_std::{Addr, Coin};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, BlockInfo, Coin, Decimal, Uint128};
+use cw20::Balance;
+use cw_storage_plus::{Item, Map};
+use cw_utils::Expiration;
 
 #[cw_serde]
 pub struct State {
+    pub creator: Addr,
+    /// Owner of the cw721 token representing the option, only they can execute it
+    pub owner: Addr,
+    /// Either native coins or a single cw20 token
+    pub collateral: Balance,
+    /// Either native coins or a single cw20 token
+    pub counter_offer: Balance,
+    pub expires: Expiration,
+    /// Native collateral is sent on instantiate, cw20 collateral has to be
+    /// sent by the creator via `Receive` before the option can be executed
+    pub funded: bool,
+    /// Number of units the option is divided into, each unit can be executed on its own
+    pub units: u64,
+    /// Units that were not executed yet
+    pub units_remaining: u64,
+    /// Options written before kinds and styles were supported are American calls
+    #[serde(default)]
+    pub kind: OptionKind,
+    #[serde(default)]
+    pub style: ExerciseStyle,
+    #[serde(default)]
+    pub settlement: Settlement,
+}
+
+/// Settlement is the same for both kinds, the owner pays the counter_offer and gets
+/// the collateral. A call locks the underlying as collateral with the strike as
+/// counter_offer, a put locks the strike as collateral with the underlying as counter_offer.
+#[cw_serde]
+#[derive(Default)]
+pub enum OptionKind {
+    #[default]
+    Call,
+    Put,
+}
+
+#[cw_serde]
+#[derive(Default)]
+pub enum ExerciseStyle {
+    /// Can be executed any time before expiry
+    #[default]
+    American,
+    /// Can only be executed in the window `[exercise_start, expires)`
+    European { exercise_start: Expiration },
+}
+
+impl ExerciseStyle {
+    /// Whether the exercise window has opened, expiry is checked separately
+    pub fn is_open(&self, block: &BlockInfo) -> bool {
+        match self {
+            ExerciseStyle::American => true,
+            ExerciseStyle::European { exercise_start } => exercise_start.is_expired(block),
+        }
+    }
+}
+
+pub const CONFIG_KEY: &str = "config";
+pub const CONFIG: Item<State> = Item::new(CONFIG_KEY);
+
+/// The option put up for sale by its owner, anyone can buy it for the price
+#[cw_serde]
+pub struct Offer {
+    pub seller: Addr,
+    pub price: Vec<Coin>,
+}
+
+pub const OFFER: Item<Offer> = Item::new("offer");
+
+#[cw_serde]
+#[derive(Default)]
+pub enum Settlement {
+    /// The owner pays the counter_offer and gets the collateral
+    #[default]
+    Physical,
+    /// The owner pays nothing and gets `max(0, spot - strike) * size` of the collateral,
+    /// `max(0, strike - spot) * size` for puts, the rest goes back to the creator.
+    /// The spot price of `base` in `quote` is queried from the `oracle` on execution.
+    Cash {
+        oracle: Addr,
+        base: String,
+        quote: String,
+        strike: Decimal,
+        size: Uint128,
+    },
+}
+
+/// Spenders allowed to transfer the option token, until it changes owner
+pub const APPROVALS: Map<&Addr, Expiration> = Map::new("approvals");
+/// Operators allowed to transfer and approve all tokens of an owner, keyed by (owner, operator)
+pub const OPERATORS: Map<(&Addr, &Addr), Expiration> = Map::new("operators");
+
+/// State of options written by the first release, which only held native coins
+/// and expire at the given block height. Only read when migrating.
+#[cw_serde]
+pub struct LegacyState {
     pub creator: Addr,
     pub owner: Addr,
     pub collateral: Vec<Coin>,
@@ -12,5 +108,4 @@ pub struct State {
     pub expires: u64,
 }
 
-pub const CONFIG_KEY: &str = "config";
-pub const CONFIG: Item<State> = Item::new(CONFIG_KEY);
+pub const LEGACY_CONFIG: Item<LegacyState> = Item::new(CONFIG_KEY);